@@ -1,18 +1,24 @@
 #![no_main]
 sp1_zkvm::entrypoint!(main);
 
-use rsp_client_executor::{io::ClientExecutorInput, ClientExecutor, LineaVariant};
+use alloy_sol_types::SolValue;
+use rsp_client_executor::{
+    io::{ClientExecutorInput, ProfilingOptions},
+    ClientExecutor, LineaVariant,
+};
 
 pub fn main() {
     // Read the input.
     let input = sp1_zkvm::io::read_vec();
     let input = bincode::deserialize::<ClientExecutorInput>(&input).unwrap();
+    let options = sp1_zkvm::io::read::<ProfilingOptions>();
 
     // Execute the block.
     let executor = ClientExecutor;
-    let header = executor.execute::<LineaVariant>(input).expect("failed to execute client");
-    let block_hash = header.hash_slow();
+    let (_, public_values, _) = executor
+        .execute_with_options::<LineaVariant>(input, options)
+        .expect("failed to execute client");
 
-    // Commit the block hash.
-    sp1_zkvm::io::commit(&block_hash);
+    // Commit the ABI-encoded public values.
+    sp1_zkvm::io::commit_slice(&public_values.abi_encode());
 }
@@ -0,0 +1,25 @@
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use alloy_sol_types::SolValue;
+use rsp_client_executor::{
+    io::{ClientExecutorInput, ProfilingOptions},
+    ClientExecutor, OptimismVariant, CHAIN_ID_OP_SEPOLIA,
+};
+
+pub fn main() {
+    // Read the input.
+    let input = sp1_zkvm::io::read_vec();
+    let input = bincode::deserialize::<ClientExecutorInput>(&input).unwrap();
+    let options = sp1_zkvm::io::read::<ProfilingOptions>();
+    assert_eq!(input.chain_spec.chain_id, CHAIN_ID_OP_SEPOLIA, "unexpected chain ID");
+
+    // Execute the block.
+    let executor = ClientExecutor;
+    let (_, public_values, _) = executor
+        .execute_with_options::<OptimismVariant>(input, options)
+        .expect("failed to execute client");
+
+    // Commit the ABI-encoded public values.
+    sp1_zkvm::io::commit_slice(&public_values.abi_encode());
+}
@@ -4,4 +4,8 @@ fn main() {
     build_program("../client-eth");
     build_program("../client-op");
     build_program("../client-linea");
+    build_program("../client-sepolia");
+    build_program("../client-holesky");
+    build_program("../client-base");
+    build_program("../client-op-sepolia");
 }
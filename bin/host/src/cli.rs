@@ -1,57 +1,142 @@
-use alloy_provider::{network::AnyNetwork, Provider as _, ReqwestProvider};
+use std::{path::PathBuf, time::Duration};
+
+use alloy_provider::Provider as _;
 use clap::Parser;
+use rsp_host_executor::provider::{RetryConfig, RetryProvider, RetryTransport};
+use rsp_rpc_db::{RpcCache, DEFAULT_PREFETCH_CONCURRENCY};
 use url::Url;
 
 /// The arguments for configuring the chain data provider.
 #[derive(Debug, Clone, Parser)]
 pub struct ProviderArgs {
-    /// The rpc url used to fetch data about the block. If not provided, will use the
-    /// RPC_{chain_id} env var.
-    #[clap(long)]
-    rpc_url: Option<Url>,
+    /// The rpc urls used to fetch data about the block, either repeated or comma-separated. Later
+    /// urls are used as fallbacks when a request to the current one keeps failing. If not
+    /// provided, will use the comma-separated RPC_{chain_id} env var.
+    #[clap(long = "rpc-url", value_delimiter = ',')]
+    rpc_urls: Vec<Url>,
     /// The chain ID. If not provided, requires the rpc_url argument to be provided.
     #[clap(long)]
     chain_id: Option<u64>,
+    /// The maximum number of in-flight RPC requests issued while prefetching state and fetching
+    /// proofs and headers.
+    #[clap(long, default_value_t = DEFAULT_PREFETCH_CONCURRENCY)]
+    rpc_concurrency: usize,
+    /// The maximum number of times an RPC request is retried after a rate limiting, server or
+    /// timeout error.
+    #[clap(long, default_value_t = RetryConfig::default().max_retries)]
+    max_retries: u32,
+    /// The initial delay between two retries in milliseconds, doubled after every failure.
+    #[clap(long, default_value_t = RetryConfig::default().initial_backoff.as_millis() as u64)]
+    initial_backoff_ms: u64,
+    /// The maximum number of RPC requests sent per second. Unlimited if not provided.
+    #[clap(long)]
+    requests_per_second: Option<u32>,
 }
 
 pub struct ProviderConfig {
-    pub rpc_url: Option<Url>,
+    pub rpc_urls: Vec<Url>,
     pub chain_id: u64,
+    pub rpc_concurrency: usize,
+    pub retry: RetryConfig,
+}
+
+impl ProviderConfig {
+    /// Creates a provider over the configured rpc urls, or `None` if there are none.
+    pub fn provider(&self) -> eyre::Result<Option<RetryProvider>> {
+        if self.rpc_urls.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(RetryTransport::provider(self.rpc_urls.clone(), self.retry.clone())?))
+    }
 }
 
 impl ProviderArgs {
     pub async fn into_provider(self) -> eyre::Result<ProviderConfig> {
-        // We don't need RPC when using cache with known chain ID, so we leave it as `Option<Url>`
-        // here and decide on whether to panic later.
+        let retry = RetryConfig {
+            max_retries: self.max_retries,
+            initial_backoff: Duration::from_millis(self.initial_backoff_ms),
+            requests_per_second: self.requests_per_second,
+            ..Default::default()
+        };
+
+        // We don't need RPC when using cache with known chain ID, so we leave the list empty
+        // here and decide on whether to fail later.
         //
         // On the other hand chain ID is always needed.
-        let (rpc_url, chain_id) = match (self.rpc_url, self.chain_id) {
-            (Some(rpc_url), Some(chain_id)) => (Some(rpc_url), chain_id),
-            (None, Some(chain_id)) => {
+        let (rpc_urls, chain_id) = match (self.rpc_urls.is_empty(), self.chain_id) {
+            (false, Some(chain_id)) => (self.rpc_urls, chain_id),
+            (true, Some(chain_id)) => {
                 match std::env::var(format!("RPC_{}", chain_id)) {
                     Ok(rpc_env_var) => {
                         // We don't always need it but if the value exists it has to be valid.
-                        (Some(Url::parse(rpc_env_var.as_str()).expect("invalid rpc url")), chain_id)
+                        let rpc_urls = rpc_env_var
+                            .split(',')
+                            .map(|url| Url::parse(url.trim()))
+                            .collect::<Result<Vec<_>, _>>()
+                            .map_err(|err| eyre::eyre!("invalid rpc url: {}", err))?;
+
+                        (rpc_urls, chain_id)
                     }
                     Err(_) => {
                         // Not having RPC is okay because we know chain ID.
-                        (None, chain_id)
+                        (vec![], chain_id)
                     }
                 }
             }
-            (Some(rpc_url), None) => {
+            (false, None) => {
                 // We can find out about chain ID from RPC.
-                let provider: ReqwestProvider<AnyNetwork> =
-                    ReqwestProvider::new_http(rpc_url.clone());
+                let provider = RetryTransport::provider(self.rpc_urls.clone(), retry.clone())?;
                 let chain_id = provider.get_chain_id().await?;
 
-                (Some(rpc_url), chain_id)
+                (self.rpc_urls, chain_id)
             }
-            (None, None) => {
+            (true, None) => {
                 eyre::bail!("either --rpc-url or --chain-id must be used")
             }
         };
 
-        Ok(ProviderConfig { rpc_url, chain_id })
+        Ok(ProviderConfig { rpc_urls, chain_id, rpc_concurrency: self.rpc_concurrency, retry })
+    }
+}
+
+/// The arguments for configuring the local caches.
+#[derive(Debug, Clone, Parser)]
+pub struct CacheArgs {
+    /// Optional path to the directory containing cached client input and RPC responses. A new
+    /// cache file will be created from RPC data if it doesn't already exist.
+    #[clap(long)]
+    pub cache_dir: Option<PathBuf>,
+    /// The maximum size of the RPC response cache in MiB. The least recently used entries are
+    /// evicted once it is exceeded. Unlimited if not provided.
+    #[clap(long)]
+    pub rpc_cache_max_size_mb: Option<u64>,
+    /// Whether to compress cached client inputs with zstd.
+    #[clap(long)]
+    pub compress_cache: bool,
+}
+
+impl CacheArgs {
+    /// Returns the cache of RPC responses shared by the blocks of all chains, if a cache
+    /// directory is configured.
+    pub fn rpc_cache_root(&self) -> Option<RpcCache> {
+        self.cache_dir
+            .as_ref()
+            .map(|cache_dir| self.with_max_size(RpcCache::new(cache_dir.join("rpc"))))
+    }
+
+    /// Returns the cache of RPC responses for the given chain, if a cache directory is
+    /// configured.
+    pub fn rpc_cache(&self, chain_id: u64) -> Option<RpcCache> {
+        self.cache_dir.as_ref().map(|cache_dir| {
+            self.with_max_size(RpcCache::new(cache_dir.join(format!("rpc/{}", chain_id))))
+        })
+    }
+
+    fn with_max_size(&self, cache: RpcCache) -> RpcCache {
+        match self.rpc_cache_max_size_mb {
+            Some(max_size_mb) => cache.with_max_size(max_size_mb * 1024 * 1024),
+            None => cache,
+        }
     }
 }
@@ -1,33 +1,135 @@
-use csv::WriterBuilder;
-use rsp_client_executor::{io::ClientExecutorInput, ChainVariant};
+use clap::Parser;
+use reth_primitives::Header;
+use rsp_client_executor::{
+    io::{BlockPublicValues, ClientExecutorInput, PrecompileAnnotation, ProfilingOptions},
+    ChainVariant, ClientExecutor, EthereumVariant, LineaVariant, OptimismVariant,
+};
+use rsp_host_executor::decode_public_values;
 use serde::{Deserialize, Serialize};
-use sp1_sdk::ExecutionReport;
-use std::{fs::OpenOptions, path::PathBuf};
+use sp1_sdk::{ExecutionReport, ProverClient, SP1Stdin};
+use std::{collections::HashMap, time::Instant};
 
-#[derive(Serialize, Deserialize)]
-struct ExecutionReportData {
-    chain_id: u64,
-    block_number: u64,
-    gas_used: u64,
-    tx_count: usize,
-    number_cycles: u64,
-    number_syscalls: u64,
-    bn_add_cycles: u64,
-    bn_mul_cycles: u64,
-    bn_pair_cycles: u64,
-    kzg_point_eval_cycles: u64,
+use crate::report::{BlockReport, ReportArgs, ReportSink, ReportSinks, TxReport};
+
+/// The data of the execution report of a block, one row of the CSV report.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExecutionReportData {
+    pub chain_id: u64,
+    pub block_number: u64,
+    /// Whether the block was executed successfully. The other fields are zero for failed blocks.
+    pub success: bool,
+    pub gas_used: u64,
+    pub tx_count: usize,
+    pub number_cycles: u64,
+    pub number_syscalls: u64,
+    pub bn_add_cycles: u64,
+    pub bn_mul_cycles: u64,
+    pub bn_pair_cycles: u64,
+    pub kzg_point_eval_cycles: u64,
+    /// The error which made the execution of the block fail, empty on success.
+    pub error: String,
+}
+
+/// The arguments for configuring how blocks are executed.
+#[derive(Debug, Clone, Parser)]
+pub struct ExecutionArgs {
+    /// Whether to execute blocks natively on the host instead of inside the zkVM. Much faster,
+    /// but doesn't report any cycle count.
+    #[clap(long)]
+    pub native: bool,
+    /// Whether to report the cycles spent by every transaction of the block and by every
+    /// top-level contract called, at the expense of a native execution of the block to obtain the
+    /// gas used by each transaction.
+    #[clap(long)]
+    pub profile_txs: bool,
+    /// How calls to precompiles are annotated with cycle tracker spans inside the zkVM: `none`,
+    /// `aggregate` (the total cycles of each precompile) or `per-call` (every call is also logged
+    /// with its cycles).
+    #[clap(long, default_value_t = PrecompileAnnotation::Aggregate)]
+    pub annotate_precompiles: PrecompileAnnotation,
+    #[clap(flatten)]
+    pub report: ReportArgs,
+}
+
+impl ExecutionArgs {
+    /// Returns the profiling options passed to the client program.
+    pub fn profiling_options(&self) -> ProfilingOptions {
+        ProfilingOptions { transactions: self.profile_txs, precompiles: self.annotate_precompiles }
+    }
 }
 
-/// Given an execution report, print it out and write it to a CSV specified by report_path.
+/// Executes the block inside the zkVM, or natively, and records the result to the report sinks.
+pub fn execute_and_report(
+    client: &ProverClient,
+    variant: ChainVariant,
+    elf: &[u8],
+    client_input: ClientExecutorInput,
+    execution: &ExecutionArgs,
+    sinks: &mut ReportSinks,
+) -> eyre::Result<ExecutionReportData> {
+    if execution.native {
+        let report_data = execute_natively(variant, client_input)?;
+        sinks.record(&BlockReport { data: report_data.clone(), ..Default::default() })?;
+        Ok(report_data)
+    } else {
+        execute_in_zkvm(client, variant, elf, client_input, execution.profiling_options(), sinks)
+    }
+}
+
+/// Returns the stdin of the client program for the given input and profiling options.
+pub fn client_stdin(client_input: &ClientExecutorInput, options: ProfilingOptions) -> SP1Stdin {
+    let mut stdin = SP1Stdin::new();
+    let buffer = bincode::serialize(client_input).unwrap();
+    stdin.write_vec(buffer);
+    stdin.write(&options);
+    stdin
+}
+
+/// Executes the block inside the zkVM, then prints the execution report and records it to the
+/// report sinks.
+pub fn execute_in_zkvm(
+    client: &ProverClient,
+    variant: ChainVariant,
+    elf: &[u8],
+    client_input: ClientExecutorInput,
+    options: ProfilingOptions,
+    sinks: &mut ReportSinks,
+) -> eyre::Result<ExecutionReportData> {
+    let stdin = client_stdin(&client_input, options);
+
+    // Only execute the program.
+    let (public_values, execution_report) = client
+        .execute(elf, stdin)
+        .run()
+        .map_err(|err| eyre::eyre!("failed to execute the client program: {}", err))?;
+
+    // Read the public values.
+    let public_values = decode_public_values(public_values.as_slice())?;
+    println!("success: block_hash={}", public_values.block_hash);
+
+    // Process the execute report, print it out, and record it to the report sinks.
+    process_execution_report(variant, client_input, execution_report, options, sinks)
+}
+
+/// Given an execution report, print it out and record it to the report sinks.
 pub fn process_execution_report(
     variant: ChainVariant,
     client_input: ClientExecutorInput,
     execution_report: ExecutionReport,
-    report_path: PathBuf,
-) -> eyre::Result<()> {
+    options: ProfilingOptions,
+    sinks: &mut ReportSinks,
+) -> eyre::Result<ExecutionReportData> {
     println!("\nExecution report:\n{}", execution_report);
 
-    let chain_id = variant.chain_id();
+    let transactions = if options.transactions {
+        let transactions = tx_reports(variant, &client_input, &execution_report.cycle_tracker)?;
+        print_tx_table(&transactions);
+        transactions
+    } else {
+        Vec::new()
+    };
+
+    let chain_id = client_input.chain_spec.chain_id;
     let executed_block = client_input.current_block;
     let block_number = executed_block.header.number;
     let gas_used = executed_block.header.gas_used;
@@ -41,14 +143,10 @@ pub fn process_execution_report(
     let kzg_point_eval_cycles =
         *execution_report.cycle_tracker.get("precompile-kzg-point-evaluation").unwrap_or(&0);
 
-    // TODO: we can track individual syscalls in our CSV once we have sp1-core as a dependency
-    // let keccak_count = execution_report.syscall_counts.get(SyscallCode::KECCAK_PERMUTE);
-    // let secp256k1_decompress_count =
-    //     execution_report.syscall_counts.get(SyscallCode::SECP256K1_DECOMPRESS);
-
     let report_data = ExecutionReportData {
         chain_id,
         block_number,
+        success: true,
         gas_used,
         tx_count,
         number_cycles,
@@ -57,17 +155,160 @@ pub fn process_execution_report(
         bn_mul_cycles,
         bn_pair_cycles,
         kzg_point_eval_cycles,
+        error: String::new(),
+    };
+
+    // The sinks other than the CSV get every cycle tracker span and every syscall made.
+    let report = BlockReport {
+        data: report_data.clone(),
+        cycle_tracker: execution_report.cycle_tracker.into_iter().collect(),
+        syscall_counts: execution_report
+            .syscall_counts
+            .iter()
+            .filter(|(_, count)| **count > 0)
+            .map(|(syscall, count)| (format!("{:?}", syscall), *count))
+            .collect(),
+        transactions,
+    };
+    sinks.record(&report)?;
+
+    Ok(report_data)
+}
+
+/// Records the failure of a block to the report sinks.
+pub fn record_execution_failure(
+    chain_id: u64,
+    block_number: u64,
+    error: &eyre::Report,
+    sinks: &mut ReportSinks,
+) -> eyre::Result<()> {
+    let report_data = ExecutionReportData {
+        chain_id,
+        block_number,
+        // Keep the whole error chain on a single line.
+        error: format!("{:#}", error).replace('\n', " "),
+        ..Default::default()
     };
+    sinks.record(&BlockReport { data: report_data, ..Default::default() })
+}
 
-    // Open the file for appending or create it if it doesn't exist
-    let file = OpenOptions::new().append(true).create(true).open(report_path)?;
+/// Joins the cycle tracker spans of the transactions of the block with the gas they used, obtained
+/// by executing the block natively.
+fn tx_reports(
+    variant: ChainVariant,
+    client_input: &ClientExecutorInput,
+    cycle_tracker: &HashMap<String, u64>,
+) -> eyre::Result<Vec<TxReport>> {
+    let (_, _, gas_used) =
+        execute_block_natively(variant, client_input.clone(), ProfilingOptions::none())?;
 
-    // Check if the file is empty
-    let file_is_empty = file.metadata()?.len() == 0;
+    let transactions = client_input.current_block.body.iter().enumerate().map(|(index, tx)| {
+        let hash = tx.hash();
+        TxReport {
+            index,
+            hash,
+            to: tx.to(),
+            gas_used: gas_used.get(index).copied().unwrap_or_default(),
+            cycles: cycle_tracker
+                .get(&format!("tx-{}-{}", index, hash))
+                .copied()
+                .unwrap_or_default(),
+        }
+    });
 
-    let mut writer = WriterBuilder::new().has_headers(file_is_empty).from_writer(file);
-    writer.serialize(report_data)?;
-    writer.flush()?;
+    Ok(transactions.collect())
+}
+
+fn print_tx_table(transactions: &[TxReport]) {
+    println!("\nTransactions:");
+    println!(
+        "  {:>5}  {:<66}  {:<42}  {:>10}  {:>12}  {:>10}",
+        "index", "hash", "to", "gas used", "cycles", "cycles/gas"
+    );
+    for tx in transactions {
+        let to = tx.to.map(|to| to.to_string()).unwrap_or_else(|| "create".to_string());
+        println!(
+            "  {:>5}  {:<66}  {:<42}  {:>10}  {:>12}  {:>10.2}",
+            tx.index,
+            tx.hash.to_string(),
+            to,
+            tx.gas_used,
+            tx.cycles,
+            tx.cycles as f64 / tx.gas_used.max(1) as f64
+        );
+    }
+    println!();
+}
 
-    Ok(())
+/// Executes the block natively on the host with the [ClientExecutor], returning the derived header,
+/// the public values and the gas used by each transaction.
+fn execute_block_natively(
+    variant: ChainVariant,
+    client_input: ClientExecutorInput,
+    options: ProfilingOptions,
+) -> eyre::Result<(Header, BlockPublicValues, Vec<u64>)> {
+    let (header, public_values, outcome) = match variant {
+        ChainVariant::Ethereum | ChainVariant::Sepolia | ChainVariant::Holesky => {
+            ClientExecutor.execute_with_options::<EthereumVariant>(client_input, options)
+        }
+        ChainVariant::Optimism | ChainVariant::Base | ChainVariant::OpSepolia => {
+            ClientExecutor.execute_with_options::<OptimismVariant>(client_input, options)
+        }
+        ChainVariant::Linea => {
+            ClientExecutor.execute_with_options::<LineaVariant>(client_input, options)
+        }
+    }?;
+
+    // Receipts only record the gas used cumulatively.
+    let mut cumulative_gas_used = 0;
+    let gas_used = outcome
+        .receipts_by_block(header.number)
+        .iter()
+        .flatten()
+        .map(|receipt| {
+            let gas_used = receipt.cumulative_gas_used - cumulative_gas_used;
+            cumulative_gas_used = receipt.cumulative_gas_used;
+            gas_used
+        })
+        .collect();
+
+    Ok((header, public_values, gas_used))
+}
+
+/// Executes the block natively on the host with the [ClientExecutor], without the zkVM, and prints
+/// the wall time, the gas used and the derived block hash. No cycles are reported.
+pub fn execute_natively(
+    variant: ChainVariant,
+    client_input: ClientExecutorInput,
+) -> eyre::Result<ExecutionReportData> {
+    let expected_block_hash = client_input.current_block.header.hash_slow();
+    let chain_id = client_input.chain_spec.chain_id;
+    let tx_count = client_input.current_block.body.len();
+
+    let start = Instant::now();
+    let (header, public_values, _) =
+        execute_block_natively(variant, client_input, ProfilingOptions::none())?;
+    let elapsed = start.elapsed();
+
+    println!(
+        "native execution: block_number={}, gas_used={}, wall_time={:?}",
+        header.number, header.gas_used, elapsed
+    );
+    if public_values.block_hash != expected_block_hash {
+        eyre::bail!(
+            "block hash mismatch: expected={}, actual={}",
+            expected_block_hash,
+            public_values.block_hash
+        );
+    }
+    println!("success: block_hash={}", public_values.block_hash);
+
+    Ok(ExecutionReportData {
+        chain_id,
+        block_number: header.number,
+        success: true,
+        gas_used: header.gas_used,
+        tx_count,
+        ..Default::default()
+    })
 }
@@ -0,0 +1,262 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use alloy_provider::{Provider, ProviderBuilder, WsConnect};
+use clap::Parser;
+use futures::{stream, stream::BoxStream, StreamExt};
+use reth_primitives::B256;
+use rsp_host_executor::provider::RetryProvider;
+use sp1_sdk::ProverClient;
+use url::Url;
+
+use crate::{
+    elf,
+    execute::{execute_and_report, record_execution_failure, ExecutionArgs},
+    input::InputSource,
+    proof::ProofArgs,
+    prove_input,
+};
+
+/// The number of most recent blocks whose hashes are remembered to detect reorgs.
+const REORG_DEPTH: usize = 64;
+
+/// Executes, and optionally proves, every new block of the chain as it is produced, recording the
+/// outcome of each block in the report.
+///
+/// Before a block is executed, its parent hash is checked against the hash of the previously
+/// executed block, even if its input couldn't be loaded. On a mismatch, the previous blocks are
+/// walked back until the chains agree: the cached inputs and RPC responses of the reorged blocks
+/// are discarded and they are executed again.
+///
+/// Blocks failing to be fetched, executed or proved are recorded as failures in the report, and
+/// following stops with an error once `max_consecutive_failures` blocks failed in a row, if set.
+pub async fn follow_chain(
+    source: InputSource,
+    start_block: Option<u64>,
+    max_consecutive_failures: Option<u32>,
+    heads: HeadArgs,
+    execution: &ExecutionArgs,
+    proof: Option<&ProofArgs>,
+) -> eyre::Result<()> {
+    let Some(provider) = source.provider_config.provider()? else {
+        eyre::bail!("an RPC URL is required to follow the chain");
+    };
+    let mut head = provider.get_block_number().await?;
+    let mut heads = HeadSource::new(provider.clone(), heads).await?;
+
+    let mut next = start_block.unwrap_or(head);
+    let mut canonical = BTreeMap::<u64, B256>::new();
+    let mut sinks = execution.report.sinks()?;
+    let client = ProverClient::new();
+    let mut consecutive_failures = 0;
+    loop {
+        while next <= head {
+            let loaded = source.load_input(next).await;
+            let (block_hash, parent_hash) = match &loaded {
+                Ok(client_input) => {
+                    let header = &client_input.current_block.header;
+                    (header.hash_slow(), header.parent_hash)
+                }
+                // The block still has to be checked against its parent, so its header is fetched
+                // on its own.
+                Err(_) => {
+                    let block = provider
+                        .get_block_by_number(next.into(), false)
+                        .await?
+                        .ok_or_else(|| eyre::eyre!("couldn't fetch block: {}", next))?;
+                    (block.header.hash, block.header.parent_hash)
+                }
+            };
+
+            let expected_parent_hash =
+                next.checked_sub(1).and_then(|parent| canonical.get(&parent));
+            if expected_parent_hash.is_some_and(|expected| *expected != parent_hash) {
+                let fork = find_reorged_blocks(&provider, &canonical, next - 1).await?;
+                tracing::warn!(
+                    "reorg detected at block {}, discarding blocks {}..={}",
+                    next,
+                    fork,
+                    next - 1
+                );
+                for height in fork..next {
+                    canonical.remove(&height);
+                    source.discard_input(height)?;
+                }
+                next = fork;
+                continue;
+            }
+
+            let failed = match loaded {
+                Ok(client_input) => {
+                    println!("\nExecuting block {} ({})", next, block_hash);
+                    let proof_input = proof.map(|_| client_input.clone());
+                    let executed = tokio::task::block_in_place(|| {
+                        execute_and_report(
+                            &client,
+                            source.variant,
+                            elf(source.variant),
+                            client_input,
+                            execution,
+                            &mut sinks,
+                        )
+                    });
+                    match executed {
+                        Ok(_) => match (proof, proof_input) {
+                            (Some(proof), Some(client_input)) => {
+                                let proved = tokio::task::block_in_place(|| {
+                                    prove_input(&source, client_input, proof)
+                                });
+                                match proved {
+                                    Ok(()) => false,
+                                    Err(err) => {
+                                        let err = err.wrap_err("failed to prove the block");
+                                        tracing::error!(
+                                            "failed to prove block {}: {:#}",
+                                            next,
+                                            err
+                                        );
+                                        record_execution_failure(
+                                            source.chain_id(),
+                                            next,
+                                            &err,
+                                            &mut sinks,
+                                        )?;
+                                        true
+                                    }
+                                }
+                            }
+                            _ => false,
+                        },
+                        Err(err) => {
+                            tracing::error!("failed to execute block {}: {:#}", next, err);
+                            record_execution_failure(source.chain_id(), next, &err, &mut sinks)?;
+                            true
+                        }
+                    }
+                }
+                Err(err) => {
+                    tracing::error!("failed to fetch block {}: {:#}", next, err);
+                    record_execution_failure(source.chain_id(), next, &err, &mut sinks)?;
+                    true
+                }
+            };
+
+            consecutive_failures = if failed { consecutive_failures + 1 } else { 0 };
+            if max_consecutive_failures.is_some_and(|max| consecutive_failures >= max) {
+                eyre::bail!(
+                    "{} consecutive blocks failed, the last one being block {}",
+                    consecutive_failures,
+                    next
+                );
+            }
+
+            canonical.insert(next, block_hash);
+            while canonical.len() > REORG_DEPTH {
+                canonical.pop_first();
+            }
+            next += 1;
+        }
+
+        head = heads.wait_for(next).await?;
+    }
+}
+
+/// Walks back from `orphaned`, a block known to have been reorged out of the canonical chain,
+/// until the remembered hash of its parent matches the chain, and returns the oldest reorged
+/// block.
+async fn find_reorged_blocks(
+    provider: &RetryProvider,
+    canonical: &BTreeMap<u64, B256>,
+    mut orphaned: u64,
+) -> eyre::Result<u64> {
+    while let Some(parent) = orphaned.checked_sub(1) {
+        let Some(parent_hash) = canonical.get(&parent) else {
+            break;
+        };
+        let block = provider
+            .get_block_by_number(parent.into(), false)
+            .await?
+            .ok_or_else(|| eyre::eyre!("couldn't fetch block: {}", parent))?;
+        if block.header.hash == *parent_hash {
+            break;
+        }
+
+        orphaned = parent;
+    }
+
+    Ok(orphaned)
+}
+
+/// The arguments for configuring how new heads of the chain are detected.
+#[derive(Debug, Clone, Parser)]
+pub struct HeadArgs {
+    /// Optional WebSocket URL to subscribe to new heads with. If not provided, or once the
+    /// subscription ends, the RPC is polled for the latest block number instead.
+    #[clap(long)]
+    pub ws_url: Option<Url>,
+    /// The interval between two polls of the latest block number, in milliseconds.
+    #[clap(long, default_value_t = 2000)]
+    pub poll_interval_ms: u64,
+}
+
+/// Where the heads of the chain are obtained from.
+struct HeadSource {
+    provider: RetryProvider,
+    poll_interval: Duration,
+    subscription: Option<BoxStream<'static, u64>>,
+}
+
+impl HeadSource {
+    async fn new(provider: RetryProvider, args: HeadArgs) -> eyre::Result<Self> {
+        let subscription = match args.ws_url {
+            Some(ws_url) => {
+                let ws_provider =
+                    ProviderBuilder::new().on_ws(WsConnect::new(ws_url.as_str())).await?;
+                let subscription = ws_provider.subscribe_blocks().await?;
+
+                // The subscription ends with the provider, so it's kept alongside.
+                let heads = stream::unfold(
+                    (ws_provider, subscription),
+                    |(ws_provider, mut subscription)| async move {
+                        let block = subscription.recv().await.ok()?;
+                        Some((block.header.number, (ws_provider, subscription)))
+                    },
+                );
+                Some(heads.boxed())
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            provider,
+            poll_interval: Duration::from_millis(args.poll_interval_ms),
+            subscription,
+        })
+    }
+
+    /// Waits until the head of the chain reaches the given block, and returns it.
+    async fn wait_for(&mut self, block_number: u64) -> eyre::Result<u64> {
+        loop {
+            let head = match &mut self.subscription {
+                Some(heads) => match heads.next().await {
+                    Some(head) => head,
+                    None => {
+                        tracing::warn!("the new heads subscription ended, polling instead");
+                        self.subscription = None;
+                        continue;
+                    }
+                },
+                None => {
+                    let head = self.provider.get_block_number().await?;
+                    if head < block_number {
+                        tokio::time::sleep(self.poll_interval).await;
+                    }
+                    head
+                }
+            };
+
+            if head >= block_number {
+                return Ok(head);
+            }
+        }
+    }
+}
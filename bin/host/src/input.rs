@@ -0,0 +1,169 @@
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use reth_primitives::Genesis;
+use rsp_client_executor::{io::ClientExecutorInput, ChainVariant};
+use rsp_host_executor::HostExecutor;
+use rsp_primitives::chain_spec::ChainSpecConfig;
+
+use crate::{
+    cli::{CacheArgs, ProviderArgs, ProviderConfig},
+    input_cache::{input_cache_path, save_input_to_cache, try_load_input_from_cache},
+};
+
+/// The arguments for obtaining client inputs, shared by all the commands operating on blocks.
+#[derive(Debug, Clone, Parser)]
+pub struct InputArgs {
+    #[clap(flatten)]
+    pub provider: ProviderArgs,
+    #[clap(flatten)]
+    pub cache: CacheArgs,
+    /// Optional path to a geth-style genesis file describing the chain spec to execute blocks
+    /// with. If not provided, the built-in spec for the chain ID is used.
+    #[clap(long)]
+    pub genesis_path: Option<PathBuf>,
+    /// Whether to build the input from the `debug_executionWitness` RPC method instead of
+    /// `eth_getProof`. Falls back to the latter if the node doesn't support it.
+    #[clap(long)]
+    pub execution_witness: bool,
+    /// Whether to trace the state changes of every transaction when the state root of a block
+    /// doesn't match, and report the first account or storage slot which differs from the chain.
+    #[clap(long)]
+    pub debug_state_root: bool,
+}
+
+/// Where client inputs are obtained from: the local cache, or the RPC if they are not cached.
+pub struct InputSource {
+    pub provider_config: ProviderConfig,
+    pub chain_spec: Option<ChainSpecConfig>,
+    pub variant: ChainVariant,
+    pub cache: CacheArgs,
+    pub execution_witness: bool,
+    pub debug_state_root: bool,
+}
+
+impl InputArgs {
+    pub async fn into_source(self) -> eyre::Result<InputSource> {
+        let provider_config = self.provider.into_provider().await?;
+
+        let chain_spec = match &self.genesis_path {
+            Some(genesis_path) => Some(load_chain_spec(genesis_path, provider_config.chain_id)?),
+            None => None,
+        };
+        let variant = resolve_variant(provider_config.chain_id, chain_spec.as_ref())?;
+
+        Ok(InputSource {
+            provider_config,
+            chain_spec,
+            variant,
+            cache: self.cache,
+            execution_witness: self.execution_witness,
+            debug_state_root: self.debug_state_root,
+        })
+    }
+}
+
+impl InputSource {
+    /// Returns the chain ID of the blocks.
+    pub fn chain_id(&self) -> u64 {
+        self.provider_config.chain_id
+    }
+
+    /// Loads the input of a block from the cache, or builds it from RPC data and caches it.
+    pub async fn load_input(&self, block_number: u64) -> eyre::Result<ClientExecutorInput> {
+        let chain_spec = self.chain_spec.clone().unwrap_or_else(|| self.variant.spec());
+        let client_input_from_cache = try_load_input_from_cache(
+            self.cache.cache_dir.as_ref(),
+            self.chain_id(),
+            block_number,
+            &chain_spec,
+        )?;
+        if let Some(client_input) = client_input_from_cache {
+            return Ok(client_input);
+        }
+
+        // Cache not found, so we need RPC.
+        let Some(provider) = self.provider_config.provider()? else {
+            eyre::bail!("cache not found and RPC URL not provided");
+        };
+
+        // Setup the host executor.
+        let mut host_executor = HostExecutor::new(provider)
+            .with_rpc_concurrency(self.provider_config.rpc_concurrency)
+            .with_execution_witness(self.execution_witness)
+            .with_debug_state_root(self.debug_state_root);
+        if let Some(rpc_cache) = self.cache.rpc_cache(self.chain_id()) {
+            host_executor = host_executor.with_cache(rpc_cache);
+        }
+        if let Some(chain_spec) = &self.chain_spec {
+            host_executor = host_executor.with_chain_spec(chain_spec.clone());
+        }
+
+        // Execute the host.
+        let client_input = host_executor.execute(block_number, self.variant).await?;
+
+        // Keep the RPC response cache within its size limit.
+        if let (Some(rpc_cache), Some(_)) =
+            (self.cache.rpc_cache_root(), self.cache.rpc_cache_max_size_mb)
+        {
+            rpc_cache.prune()?;
+        }
+
+        if let Some(cache_dir) = &self.cache.cache_dir {
+            save_input_to_cache(
+                cache_dir,
+                self.chain_id(),
+                &client_input,
+                self.cache.compress_cache,
+            )?;
+        }
+
+        Ok(client_input)
+    }
+
+    /// Removes the cached input of a block and the RPC responses cached at its height, e.g. once
+    /// it was reorged out of the canonical chain.
+    pub fn discard_input(&self, block_number: u64) -> eyre::Result<()> {
+        if let Some(cache_dir) = &self.cache.cache_dir {
+            let path = input_cache_path(cache_dir, self.chain_id(), block_number);
+            if path.exists() {
+                std::fs::remove_file(path)?;
+            }
+        }
+        if let Some(rpc_cache) = self.cache.rpc_cache(self.chain_id()) {
+            rpc_cache.remove_height(block_number)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Loads the chain spec described by a genesis file, checking that it matches the chain ID.
+pub fn load_chain_spec(genesis_path: &Path, chain_id: u64) -> eyre::Result<ChainSpecConfig> {
+    let genesis: Genesis = serde_json::from_reader(std::fs::File::open(genesis_path)?)?;
+    let chain_spec = ChainSpecConfig::from_genesis(&genesis)?;
+    if chain_spec.chain_id != chain_id {
+        eyre::bail!(
+            "genesis chain ID mismatch: expected={}, actual={}",
+            chain_id,
+            chain_spec.chain_id
+        );
+    }
+
+    Ok(chain_spec)
+}
+
+/// Identifies the variant of a chain from its ID, or from its custom chain spec if unknown.
+pub fn resolve_variant(
+    chain_id: u64,
+    chain_spec: Option<&ChainSpecConfig>,
+) -> eyre::Result<ChainVariant> {
+    Ok(match (ChainVariant::from_chain_id(chain_id), chain_spec) {
+        (Some(variant), _) => variant,
+        (_, Some(chain_spec)) if chain_spec.is_optimism() => ChainVariant::Optimism,
+        (_, Some(_)) => ChainVariant::Ethereum,
+        (_, None) => {
+            eyre::bail!("unknown chain ID: {}", chain_id);
+        }
+    })
+}
@@ -0,0 +1,343 @@
+use std::{
+    fs::File,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+use reth_primitives::{keccak256, B256};
+use rsp_client_executor::io::ClientExecutorInput;
+use rsp_primitives::chain_spec::ChainSpecConfig;
+use serde::{Deserialize, Serialize};
+
+/// The magic bytes every cached input file starts with.
+const MAGIC: &[u8; 8] = b"RSPINPUT";
+
+/// The version of the cached input file format, to be bumped on any change to the layout below.
+const FORMAT_VERSION: u32 = 2;
+
+/// The zstd compression level of compressed cached inputs.
+const ZSTD_LEVEL: i32 = 3;
+
+/// The header of a cached input file.
+///
+/// A file is laid out as the [MAGIC] bytes, the little-endian [FORMAT_VERSION], the
+/// bincode-encoded header and finally the bincode-encoded [ClientExecutorInput], optionally
+/// compressed with zstd.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputCacheHeader {
+    /// The chain ID of the block.
+    pub chain_id: u64,
+    /// The number of the block.
+    pub block_number: u64,
+    /// The hash of the block.
+    pub block_hash: B256,
+    /// The version of rsp which generated the input.
+    pub rsp_version: String,
+    /// Whether the input is compressed with zstd.
+    pub compressed: bool,
+    /// The keccak256 hash of the input, as stored in the file.
+    pub checksum: B256,
+}
+
+/// Returns the path of the cached input of a block.
+pub fn input_cache_path(cache_dir: &Path, chain_id: u64, block_number: u64) -> PathBuf {
+    cache_dir.join(format!("input/{}/{}.bin", chain_id, block_number))
+}
+
+/// Loads the cached input of a block, if any.
+///
+/// Files that are not valid cached inputs for the block, that were generated by another version
+/// of rsp or under another chain spec, are ignored so that the input is fetched again.
+pub fn try_load_input_from_cache(
+    cache_dir: Option<&PathBuf>,
+    chain_id: u64,
+    block_number: u64,
+    chain_spec: &ChainSpecConfig,
+) -> eyre::Result<Option<ClientExecutorInput>> {
+    let Some(cache_dir) = cache_dir else {
+        return Ok(None);
+    };
+
+    let cache_path = input_cache_path(cache_dir, chain_id, block_number);
+    if !cache_path.exists() {
+        return Ok(None);
+    }
+
+    let result = read_input(&cache_path).and_then(|(header, client_input)| {
+        if header.chain_id != chain_id {
+            eyre::bail!("chain ID mismatch: expected={}, actual={}", chain_id, header.chain_id);
+        }
+        if header.block_number != block_number {
+            eyre::bail!(
+                "block number mismatch: expected={}, actual={}",
+                block_number,
+                header.block_number
+            );
+        }
+        if client_input.chain_spec.hash() != chain_spec.hash() {
+            eyre::bail!(
+                "chain spec mismatch: expected={}, actual={}",
+                chain_spec.hash(),
+                client_input.chain_spec.hash()
+            );
+        }
+
+        Ok(client_input)
+    });
+
+    match result {
+        Ok(client_input) => Ok(Some(client_input)),
+        Err(err) => {
+            tracing::warn!("ignoring invalid cached input {}: {}", cache_path.display(), err);
+            Ok(None)
+        }
+    }
+}
+
+/// Saves the input of a block to the cache.
+pub fn save_input_to_cache(
+    cache_dir: &Path,
+    chain_id: u64,
+    client_input: &ClientExecutorInput,
+    compress: bool,
+) -> eyre::Result<()> {
+    let block_number = client_input.current_block.number;
+    let cache_path = input_cache_path(cache_dir, chain_id, block_number);
+    std::fs::create_dir_all(cache_path.parent().expect("cache path has a parent"))?;
+
+    let mut payload = bincode::serialize(client_input)?;
+    if compress {
+        payload = zstd::encode_all(payload.as_slice(), ZSTD_LEVEL)?;
+    }
+
+    let header = InputCacheHeader {
+        chain_id,
+        block_number,
+        block_hash: client_input.current_block.header.hash_slow(),
+        rsp_version: env!("CARGO_PKG_VERSION").to_string(),
+        compressed: compress,
+        checksum: keccak256(&payload),
+    };
+
+    let mut file = File::create(cache_path)?;
+    file.write_all(MAGIC)?;
+    file.write_all(&FORMAT_VERSION.to_le_bytes())?;
+    bincode::serialize_into(&mut file, &header)?;
+    file.write_all(&payload)?;
+
+    Ok(())
+}
+
+/// Reads a cached input file, validating its format, version, checksum and block hash.
+pub fn read_input(path: &Path) -> eyre::Result<(InputCacheHeader, ClientExecutorInput)> {
+    let mut file = File::open(path)?;
+
+    let mut magic = [0u8; 8];
+    file.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        eyre::bail!("not an rsp input file");
+    }
+
+    let mut version = [0u8; 4];
+    file.read_exact(&mut version)?;
+    let version = u32::from_le_bytes(version);
+    if version != FORMAT_VERSION {
+        eyre::bail!("format version mismatch: expected={}, actual={}", FORMAT_VERSION, version);
+    }
+
+    let header: InputCacheHeader = bincode::deserialize_from(&mut file)?;
+    if header.rsp_version != env!("CARGO_PKG_VERSION") {
+        eyre::bail!(
+            "rsp version mismatch: expected={}, actual={}",
+            env!("CARGO_PKG_VERSION"),
+            header.rsp_version
+        );
+    }
+
+    let mut payload = Vec::new();
+    file.read_to_end(&mut payload)?;
+    let checksum = keccak256(&payload);
+    if checksum != header.checksum {
+        eyre::bail!("checksum mismatch: expected={}, actual={}", header.checksum, checksum);
+    }
+    if header.compressed {
+        payload = zstd::decode_all(payload.as_slice())?;
+    }
+
+    let client_input: ClientExecutorInput = bincode::deserialize(&payload)?;
+    let block_hash = client_input.current_block.header.hash_slow();
+    if block_hash != header.block_hash {
+        eyre::bail!("block hash mismatch: expected={}, actual={}", header.block_hash, block_hash);
+    }
+
+    Ok((header, client_input))
+}
+
+#[cfg(test)]
+mod tests {
+    use reth_primitives::{constants::EMPTY_ROOT_HASH, Block, Header};
+    use rsp_mpt::EthereumState;
+
+    use super::*;
+
+    fn test_input() -> ClientExecutorInput {
+        ClientExecutorInput {
+            chain_spec: ChainSpecConfig::mainnet(),
+            current_block: Block {
+                header: Header { number: 42, gas_limit: 30_000_000, ..Default::default() },
+                ..Default::default()
+            },
+            ancestor_headers: vec![Header { number: 41, ..Default::default() }],
+            parent_state: EthereumState::from_proofs(EMPTY_ROOT_HASH, &Default::default()).unwrap(),
+            state_requests: Default::default(),
+            bytecodes: vec![],
+        }
+    }
+
+    /// Returns an empty directory unique to the test.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "rsp-input-cache-test-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Writes a cached input file with the given format version, header and payload.
+    fn write_raw(path: &Path, version: u32, header: &InputCacheHeader, payload: &[u8]) {
+        let mut file = File::create(path).unwrap();
+        file.write_all(MAGIC).unwrap();
+        file.write_all(&version.to_le_bytes()).unwrap();
+        bincode::serialize_into(&mut file, header).unwrap();
+        file.write_all(payload).unwrap();
+    }
+
+    fn test_header(client_input: &ClientExecutorInput, payload: &[u8]) -> InputCacheHeader {
+        InputCacheHeader {
+            chain_id: 1,
+            block_number: client_input.current_block.number,
+            block_hash: client_input.current_block.header.hash_slow(),
+            rsp_version: env!("CARGO_PKG_VERSION").to_string(),
+            compressed: false,
+            checksum: keccak256(payload),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let dir = test_dir("round-trip");
+        let client_input = test_input();
+
+        for compress in [false, true] {
+            save_input_to_cache(&dir, 1, &client_input, compress).unwrap();
+            let (header, loaded) = read_input(&input_cache_path(&dir, 1, 42)).unwrap();
+            assert_eq!(header.compressed, compress);
+            assert_eq!(loaded, client_input);
+
+            let loaded =
+                try_load_input_from_cache(Some(&dir), 1, 42, &ChainSpecConfig::mainnet()).unwrap();
+            assert_eq!(loaded, Some(client_input.clone()));
+        }
+
+        // Missing files and inputs built under another chain spec are cache misses.
+        assert_eq!(
+            try_load_input_from_cache(Some(&dir), 1, 43, &ChainSpecConfig::mainnet()).unwrap(),
+            None
+        );
+        assert_eq!(
+            try_load_input_from_cache(Some(&dir), 1, 42, &ChainSpecConfig::sepolia()).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_header_mismatch() {
+        let dir = test_dir("header-mismatch");
+        save_input_to_cache(&dir, 1, &test_input(), false).unwrap();
+
+        // A file moved to the path of another chain or block is not loaded.
+        let path = input_cache_path(&dir, 1, 42);
+        for (chain_id, block_number) in [(10, 42), (1, 43)] {
+            let other_path = input_cache_path(&dir, chain_id, block_number);
+            std::fs::create_dir_all(other_path.parent().unwrap()).unwrap();
+            std::fs::copy(&path, &other_path).unwrap();
+            let loaded = try_load_input_from_cache(
+                Some(&dir),
+                chain_id,
+                block_number,
+                &ChainSpecConfig::mainnet(),
+            )
+            .unwrap();
+            assert_eq!(loaded, None);
+        }
+
+        // A block hash not matching the input is rejected.
+        let client_input = test_input();
+        let payload = bincode::serialize(&client_input).unwrap();
+        let header = InputCacheHeader {
+            block_hash: B256::repeat_byte(1),
+            ..test_header(&client_input, &payload)
+        };
+        write_raw(&path, FORMAT_VERSION, &header, &payload);
+        let err = read_input(&path).unwrap_err();
+        assert!(err.to_string().contains("block hash mismatch"), "{}", err);
+
+        // So is another version of rsp.
+        let header = InputCacheHeader {
+            rsp_version: "0.0.0".to_string(),
+            ..test_header(&client_input, &payload)
+        };
+        write_raw(&path, FORMAT_VERSION, &header, &payload);
+        let err = read_input(&path).unwrap_err();
+        assert!(err.to_string().contains("rsp version mismatch"), "{}", err);
+    }
+
+    #[test]
+    fn test_checksum_mismatch() {
+        let dir = test_dir("checksum-mismatch");
+        save_input_to_cache(&dir, 1, &test_input(), false).unwrap();
+
+        let path = input_cache_path(&dir, 1, 42);
+        let mut bytes = std::fs::read(&path).unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        std::fs::write(&path, bytes).unwrap();
+
+        let err = read_input(&path).unwrap_err();
+        assert!(err.to_string().contains("checksum mismatch"), "{}", err);
+    }
+
+    #[test]
+    fn test_format_version_mismatch() {
+        let dir = test_dir("format-version-mismatch");
+        let client_input = test_input();
+        let payload = bincode::serialize(&client_input).unwrap();
+
+        let path = input_cache_path(&dir, 1, 42);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        write_raw(&path, FORMAT_VERSION + 1, &test_header(&client_input, &payload), &payload);
+
+        let err = read_input(&path).unwrap_err();
+        assert!(err.to_string().contains("format version mismatch"), "{}", err);
+    }
+
+    #[test]
+    fn test_corrupt_zstd_body() {
+        let dir = test_dir("corrupt-zstd-body");
+        let client_input = test_input();
+
+        // The checksum matches the stored body, which is not valid zstd.
+        let payload = b"not a zstd frame".to_vec();
+        let header = InputCacheHeader { compressed: true, ..test_header(&client_input, &payload) };
+        let path = input_cache_path(&dir, 1, 42);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        write_raw(&path, FORMAT_VERSION, &header, &payload);
+
+        assert!(read_input(&path).is_err());
+        let loaded =
+            try_load_input_from_cache(Some(&dir), 1, 42, &ChainSpecConfig::mainnet()).unwrap();
+        assert_eq!(loaded, None);
+    }
+}
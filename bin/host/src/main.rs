@@ -1,41 +1,151 @@
-use alloy_provider::ReqwestProvider;
-use clap::Parser;
-use reth_primitives::B256;
+use clap::{Parser, Subcommand};
+use reth_primitives::{keccak256, B256};
 use rsp_client_executor::{
-    io::ClientExecutorInput, ChainVariant, CHAIN_ID_ETH_MAINNET, CHAIN_ID_LINEA_MAINNET,
-    CHAIN_ID_OP_MAINNET,
+    io::{ClientExecutorInput, ProfilingOptions},
+    ChainVariant,
 };
-use rsp_host_executor::HostExecutor;
-use sp1_sdk::{include_elf, ProverClient, SP1Stdin};
-use std::path::PathBuf;
+use rsp_host_executor::decode_public_values;
+use sp1_sdk::{include_elf, HashableKey, ProverClient};
+use std::path::{Path, PathBuf};
 use tracing_subscriber::{
     filter::EnvFilter, fmt, prelude::__tracing_subscriber_SubscriberExt, util::SubscriberInitExt,
 };
 
 mod execute;
-use execute::process_execution_report;
+use execute::{client_stdin, execute_and_report, ExecutionArgs};
+
+mod follow;
+use follow::{follow_chain, HeadArgs};
+
+mod input;
+use input::{load_chain_spec, resolve_variant, InputArgs, InputSource};
+
+mod input_cache;
+
+mod proof;
+use proof::{load_proof, load_vkey, proof_dir, save_proof, ProofArgs, ProofMetadata, ProofMode};
+
+mod range;
+use range::run_range;
+
+mod report;
+
+mod verify;
+use verify::verify_input;
 
 mod cli;
-use cli::ProviderArgs;
+use cli::CacheArgs;
 
 /// The arguments for the host executable.
 #[derive(Debug, Clone, Parser)]
 struct HostArgs {
-    /// The block number of the block to execute.
-    #[clap(long)]
-    block_number: u64,
-    #[clap(flatten)]
-    provider: ProviderArgs,
-    /// Whether to generate a proof or just execute the block.
-    #[clap(long)]
-    prove: bool,
-    /// Optional path to the directory containing cached client input. A new cache file will be
-    /// created from RPC data if it doesn't already exist.
-    #[clap(long)]
-    cache_dir: Option<PathBuf>,
-    /// The path to the CSV file containing the execution data.
-    #[clap(long, default_value = "report.csv")]
-    report_path: PathBuf,
+    #[clap(subcommand)]
+    command: HostCommand,
+}
+
+/// The commands of the host executable.
+#[derive(Debug, Clone, Subcommand)]
+enum HostCommand {
+    /// Builds the input of a block and saves it to the cache, without executing it.
+    Fetch {
+        /// The block number of the block to fetch.
+        #[clap(long)]
+        block_number: u64,
+        #[clap(flatten)]
+        input: InputArgs,
+    },
+    /// Executes a block inside the zkVM (or natively) and reports the number of cycles.
+    Execute {
+        /// The block number of the block to execute.
+        #[clap(long)]
+        block_number: u64,
+        #[clap(flatten)]
+        input: InputArgs,
+        #[clap(flatten)]
+        execution: ExecutionArgs,
+    },
+    /// Generates a proof of the execution of a block and saves it along with its public values.
+    Prove {
+        /// The block number of the block to prove.
+        #[clap(long)]
+        block_number: u64,
+        #[clap(flatten)]
+        input: InputArgs,
+        #[clap(flatten)]
+        proof: ProofArgs,
+    },
+    /// Verifies a saved proof against the verification key of its chain and an expected block
+    /// hash.
+    Verify {
+        /// The directory containing the proof saved by the `prove` command.
+        #[clap(long)]
+        proof_dir: PathBuf,
+        /// Optional path to the verifying key to check the proof against. If not provided, the
+        /// verifying key of the current client program of the chain is used.
+        #[clap(long)]
+        vkey_path: Option<PathBuf>,
+        /// The expected hash of the proven block.
+        #[clap(long)]
+        block_hash: B256,
+        /// Optional path to the genesis file of the chain, required for chains which are not
+        /// supported out of the box.
+        #[clap(long)]
+        genesis_path: Option<PathBuf>,
+    },
+    /// Executes every block of an inclusive range of blocks, recording the outcome of each block
+    /// in the report. Blocks already in the report are skipped, so interrupted runs can be
+    /// resumed.
+    Range {
+        /// The first block of the range.
+        #[clap(long)]
+        start_block: u64,
+        /// The last block of the range.
+        #[clap(long)]
+        end_block: u64,
+        /// The number of block inputs fetched concurrently. Blocks are still executed one at a
+        /// time, in order.
+        #[clap(long, default_value_t = 1)]
+        parallelism: usize,
+        /// Whether to execute again the blocks recorded as failed in the report.
+        #[clap(long)]
+        retry_failures: bool,
+        #[clap(flatten)]
+        input: InputArgs,
+        #[clap(flatten)]
+        execution: ExecutionArgs,
+    },
+    /// Executes, and optionally proves, every new block of the chain as it is produced, recording
+    /// the outcome of each block in the report. Reorged blocks are detected and executed again.
+    Follow {
+        /// The first block to execute. Defaults to the latest block of the chain.
+        #[clap(long)]
+        start_block: Option<u64>,
+        /// Stop with an error once this many blocks in a row failed to be fetched, executed or
+        /// proved. By default, failures are only recorded in the report.
+        #[clap(long)]
+        max_consecutive_failures: Option<u32>,
+        #[clap(flatten)]
+        heads: HeadArgs,
+        /// Whether to also generate a proof of each block, saved as by the `prove` command.
+        #[clap(long)]
+        prove: bool,
+        #[clap(flatten)]
+        proof: ProofArgs,
+        #[clap(flatten)]
+        input: InputArgs,
+        #[clap(flatten)]
+        execution: ExecutionArgs,
+    },
+    /// Checks, without any RPC, that a cached client input is consistent with its own block
+    /// header, and prints a summary of its witness.
+    VerifyInput {
+        /// The path to the cached client input, e.g. `<cache-dir>/input/<chain-id>/<block>.bin`.
+        #[clap(long)]
+        input_path: PathBuf,
+    },
+    /// Evicts the least recently used entries of the RPC response cache until it fits within
+    /// `--rpc-cache-max-size-mb`.
+    PruneCache(CacheArgs),
 }
 
 #[tokio::main]
@@ -52,116 +162,229 @@ async fn main() -> eyre::Result<()> {
 
     // Parse the command line arguments.
     let args = HostArgs::parse();
-    let provider_config = args.provider.into_provider().await?;
-
-    let variant = match provider_config.chain_id {
-        CHAIN_ID_ETH_MAINNET => ChainVariant::Ethereum,
-        CHAIN_ID_OP_MAINNET => ChainVariant::Optimism,
-        CHAIN_ID_LINEA_MAINNET => ChainVariant::Linea,
-        _ => {
-            eyre::bail!("unknown chain ID: {}", provider_config.chain_id);
-        }
-    };
-
-    let client_input_from_cache = try_load_input_from_cache(
-        args.cache_dir.as_ref(),
-        provider_config.chain_id,
-        args.block_number,
-    )?;
-
-    let client_input = match (client_input_from_cache, provider_config.rpc_url) {
-        (Some(client_input_from_cache), _) => client_input_from_cache,
-        (None, Some(rpc_url)) => {
-            // Cache not found but we have RPC
-            // Setup the provider.
-            let provider = ReqwestProvider::new_http(rpc_url);
-
-            // Setup the host executor.
-            let host_executor = HostExecutor::new(provider);
-
-            // Execute the host.
-            let client_input = host_executor
-                .execute(args.block_number, variant)
-                .await
-                .expect("failed to execute host");
-
-            if let Some(cache_dir) = args.cache_dir {
-                let input_folder = cache_dir.join(format!("input/{}", provider_config.chain_id));
-                if !input_folder.exists() {
-                    std::fs::create_dir_all(&input_folder)?;
-                }
-
-                let input_path = input_folder.join(format!("{}.bin", args.block_number));
-                let mut cache_file = std::fs::File::create(input_path)?;
+    match args.command {
+        HostCommand::Fetch { block_number, input } => {
+            if input.cache.cache_dir.is_none() {
+                eyre::bail!("--cache-dir is required to fetch inputs");
+            }
 
-                bincode::serialize_into(&mut cache_file, &client_input)?;
+            let source = input.into_source().await?;
+            source.load_input(block_number).await?;
+            println!("success: cached the input of block {}", block_number);
+        }
+        HostCommand::Execute { block_number, input, execution } => {
+            let source = input.into_source().await?;
+            let client = ProverClient::new();
+            execute_block(&client, &source, block_number, &execution).await?;
+        }
+        HostCommand::Prove { block_number, input, proof } => {
+            let source = input.into_source().await?;
+            let client_input = source.load_input(block_number).await?;
+            prove_input(&source, client_input, &proof)?;
+        }
+        HostCommand::Verify { proof_dir, vkey_path, block_hash, genesis_path } => {
+            verify_proof(&proof_dir, vkey_path.as_ref(), block_hash, genesis_path.as_ref())?;
+        }
+        HostCommand::Range {
+            start_block,
+            end_block,
+            parallelism,
+            retry_failures,
+            input,
+            execution,
+        } => {
+            if start_block > end_block {
+                eyre::bail!("the start block must not be after the end block");
             }
 
-            client_input
+            let source = input.into_source().await?;
+            run_range(source, start_block, end_block, parallelism, retry_failures, &execution)
+                .await?;
         }
-        (None, None) => {
-            eyre::bail!("cache not found and RPC URL not provided")
+        HostCommand::Follow {
+            start_block,
+            max_consecutive_failures,
+            heads,
+            prove,
+            proof,
+            input,
+            execution,
+        } => {
+            let source = input.into_source().await?;
+            follow_chain(
+                source,
+                start_block,
+                max_consecutive_failures,
+                heads,
+                &execution,
+                prove.then_some(&proof),
+            )
+            .await?;
         }
-    };
+        HostCommand::VerifyInput { input_path } => verify_input(&input_path)?,
+        HostCommand::PruneCache(cache_args) => {
+            let (Some(rpc_cache), Some(_)) =
+                (cache_args.rpc_cache_root(), cache_args.rpc_cache_max_size_mb)
+            else {
+                eyre::bail!(
+                    "--cache-dir and --rpc-cache-max-size-mb are required to prune the cache"
+                );
+            };
+            let stats = rpc_cache.prune()?;
+            println!(
+                "pruned {} entries ({} bytes), {} bytes left",
+                stats.removed_entries, stats.removed_bytes, stats.remaining_bytes
+            );
+        }
+    }
 
-    // Generate the proof.
-    let client = ProverClient::new();
+    Ok(())
+}
 
-    // Setup the proving key and verification key.
-    let (pk, vk) = client.setup(match variant {
+/// Returns the client program of a chain variant.
+fn elf(variant: ChainVariant) -> &'static [u8] {
+    match variant {
         ChainVariant::Ethereum => include_elf!("rsp-client-eth"),
         ChainVariant::Optimism => include_elf!("rsp-client-op"),
         ChainVariant::Linea => include_elf!("rsp-client-linea"),
-    });
+        ChainVariant::Sepolia => include_elf!("rsp-client-sepolia"),
+        ChainVariant::Holesky => include_elf!("rsp-client-holesky"),
+        ChainVariant::Base => include_elf!("rsp-client-base"),
+        ChainVariant::OpSepolia => include_elf!("rsp-client-op-sepolia"),
+    }
+}
 
-    // Execute the block inside the zkVM.
-    let mut stdin = SP1Stdin::new();
-    let buffer = bincode::serialize(&client_input).unwrap();
-    stdin.write_vec(buffer);
+/// Executes a block inside the zkVM, or natively, and reports the results.
+async fn execute_block(
+    client: &ProverClient,
+    source: &InputSource,
+    block_number: u64,
+    execution: &ExecutionArgs,
+) -> eyre::Result<()> {
+    let client_input = source.load_input(block_number).await?;
 
-    // Only execute the program.
-    let (mut public_values, execution_report) =
-        client.execute(&pk.elf, stdin.clone()).run().unwrap();
+    let mut sinks = execution.report.sinks()?;
+    execute_and_report(
+        client,
+        source.variant,
+        elf(source.variant),
+        client_input,
+        execution,
+        &mut sinks,
+    )?;
 
-    // Read the block hash.
-    let block_hash = public_values.read::<B256>();
-    println!("success: block_hash={block_hash}");
+    Ok(())
+}
 
-    // Process the execute report, print it out, and save data to a CSV specified by
-    // report_path.
-    process_execution_report(variant, client_input, execution_report, args.report_path)?;
+/// Generates a proof of a block, and saves it along with its public values, the verifying key and
+/// metadata to the output directory.
+fn prove_input(
+    source: &InputSource,
+    client_input: ClientExecutorInput,
+    proof_args: &ProofArgs,
+) -> eyre::Result<()> {
+    let block_number = client_input.current_block.header.number;
+    let proof_mode = proof_args.proof_mode;
 
-    if args.prove {
-        // Actually generate the proof. It is strongly recommended you use the network prover
-        // given the size of these programs.
-        println!("Starting proof generation.");
-        let proof = client.prove(&pk, stdin).compressed().run().expect("Proving should work.");
-        println!("Proof generation finished.");
+    let client = ProverClient::new();
 
-        client.verify(&proof, &vk).expect("proof verification should succeed");
-    }
+    // Setup the proving key and verification key.
+    let elf = elf(source.variant);
+    let (pk, vk) = client.setup(elf);
+
+    let stdin = client_stdin(&client_input, ProfilingOptions::none());
+
+    // Actually generate the proof. It is strongly recommended you use the network prover given the
+    // size of these programs.
+    println!("Starting {} proof generation.", proof_mode);
+    let prove = client.prove(&pk, stdin);
+    let prove = match proof_mode {
+        ProofMode::Core => prove.core(),
+        ProofMode::Compressed => prove.compressed(),
+        ProofMode::Plonk => prove.plonk(),
+        ProofMode::Groth16 => prove.groth16(),
+    };
+    let proof = prove.run().map_err(|err| eyre::eyre!("failed to generate the proof: {}", err))?;
+    println!("Proof generation finished.");
+
+    client.verify(&proof, &vk).map_err(|err| eyre::eyre!("invalid proof: {}", err))?;
+
+    let public_values = decode_public_values(proof.public_values.as_slice())?;
+    println!("success: block_hash={}", public_values.block_hash);
+
+    // Save the proof along with everything needed to verify it.
+    let metadata = ProofMetadata {
+        chain_id: source.chain_id(),
+        block_number,
+        block_hash: public_values.block_hash,
+        elf_hash: keccak256(elf),
+        vkey_hash: vk.bytes32(),
+        proof_mode,
+        rsp_version: env!("CARGO_PKG_VERSION").to_string(),
+    };
+    let proof_dir = proof_dir(&proof_args.output_dir, source.chain_id(), block_number);
+    save_proof(&proof_dir, &proof, &vk, &metadata)?;
+    println!("Proof saved to {}.", proof_dir.display());
 
     Ok(())
 }
 
-fn try_load_input_from_cache(
-    cache_dir: Option<&PathBuf>,
-    chain_id: u64,
-    block_number: u64,
-) -> eyre::Result<Option<ClientExecutorInput>> {
-    Ok(if let Some(cache_dir) = cache_dir {
-        let cache_path = cache_dir.join(format!("input/{}/{}.bin", chain_id, block_number));
-
-        if cache_path.exists() {
-            // TODO: prune the cache if invalid instead
-            let mut cache_file = std::fs::File::open(cache_path)?;
-            let client_input: ClientExecutorInput = bincode::deserialize_from(&mut cache_file)?;
-
-            Some(client_input)
-        } else {
-            None
+/// Verifies a saved proof against a verifying key and an expected block hash.
+fn verify_proof(
+    proof_dir: &Path,
+    vkey_path: Option<&PathBuf>,
+    block_hash: B256,
+    genesis_path: Option<&PathBuf>,
+) -> eyre::Result<()> {
+    let (proof, metadata) = load_proof(proof_dir)?;
+    let public_values = decode_public_values(proof.public_values.as_slice())?;
+    if (public_values.chain_id, public_values.block_number, public_values.block_hash) !=
+        (metadata.chain_id, metadata.block_number, metadata.block_hash)
+    {
+        eyre::bail!("the public values of the proof don't match its metadata");
+    }
+
+    let client = ProverClient::new();
+    let vk = match vkey_path {
+        Some(vkey_path) => load_vkey(vkey_path)?,
+        None => {
+            let chain_spec = match genesis_path {
+                Some(genesis_path) => {
+                    let chain_spec = load_chain_spec(genesis_path, public_values.chain_id)?;
+                    if chain_spec.hash() != public_values.chain_spec_hash {
+                        eyre::bail!(
+                            "the proof was not generated with the chain spec of the genesis file"
+                        );
+                    }
+
+                    Some(chain_spec)
+                }
+                None => None,
+            };
+            let variant = resolve_variant(public_values.chain_id, chain_spec.as_ref())?;
+            if variant.program_id() != public_values.program_id {
+                eyre::bail!("the proof was not generated by the client program of the chain");
+            }
+
+            client.setup(elf(variant)).1
         }
-    } else {
-        None
-    })
+    };
+    client.verify(&proof, &vk).map_err(|err| eyre::eyre!("invalid proof: {}", err))?;
+
+    if public_values.block_hash != block_hash {
+        eyre::bail!(
+            "block hash mismatch: expected={}, actual={}",
+            block_hash,
+            public_values.block_hash
+        );
+    }
+    println!(
+        "success: {} proof of block {} ({}) on chain {} is valid",
+        metadata.proof_mode,
+        public_values.block_number,
+        public_values.block_hash,
+        public_values.chain_id
+    );
+
+    Ok(())
 }
@@ -0,0 +1,111 @@
+use std::{
+    fmt,
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+use clap::{Parser, ValueEnum};
+use reth_primitives::B256;
+use serde::{Deserialize, Serialize};
+use sp1_sdk::{SP1ProofWithPublicValues, SP1VerifyingKey};
+
+const PROOF_FILE: &str = "proof.bin";
+const PUBLIC_VALUES_FILE: &str = "public-values.bin";
+const VKEY_FILE: &str = "vkey.bin";
+const METADATA_FILE: &str = "metadata.json";
+
+/// The arguments for configuring how proofs are generated and where they are saved.
+#[derive(Debug, Clone, Parser)]
+pub struct ProofArgs {
+    /// The directory the proof, its public values, the verifying key and metadata are saved to,
+    /// under `<chain-id>/<block-number>/`.
+    #[clap(long, default_value = "proofs")]
+    pub output_dir: PathBuf,
+    /// The kind of proof to generate. PLONK and Groth16 proofs can be verified on-chain.
+    #[clap(long, value_enum, default_value_t = ProofMode::Compressed)]
+    pub proof_mode: ProofMode,
+}
+
+/// The kind of proof to generate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProofMode {
+    /// A STARK proof for each shard of the execution. Fastest to generate, but large.
+    Core,
+    /// A single recursive STARK proof of constant size.
+    Compressed,
+    /// A PLONK proof, which can be verified on-chain.
+    Plonk,
+    /// A Groth16 proof, which can be verified on-chain at the lowest cost.
+    Groth16,
+}
+
+impl fmt::Display for ProofMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode = match self {
+            Self::Core => "core",
+            Self::Compressed => "compressed",
+            Self::Plonk => "plonk",
+            Self::Groth16 => "groth16",
+        };
+        f.write_str(mode)
+    }
+}
+
+/// The metadata saved along with a proof.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofMetadata {
+    /// The chain ID of the proven block.
+    pub chain_id: u64,
+    /// The number of the proven block.
+    pub block_number: u64,
+    /// The hash of the proven block.
+    pub block_hash: B256,
+    /// The keccak256 hash of the client program.
+    pub elf_hash: B256,
+    /// The hash of the verifying key, as expected by on-chain verifiers.
+    pub vkey_hash: String,
+    /// The kind of proof.
+    pub proof_mode: ProofMode,
+    /// The version of rsp which generated the proof.
+    pub rsp_version: String,
+}
+
+/// Returns the directory containing the proof of a block.
+pub fn proof_dir(output_dir: &Path, chain_id: u64, block_number: u64) -> PathBuf {
+    output_dir.join(chain_id.to_string()).join(block_number.to_string())
+}
+
+/// Saves a proof, its public values, the verifying key and the metadata to a directory.
+pub fn save_proof(
+    dir: &Path,
+    proof: &SP1ProofWithPublicValues,
+    vk: &SP1VerifyingKey,
+    metadata: &ProofMetadata,
+) -> eyre::Result<()> {
+    std::fs::create_dir_all(dir)?;
+
+    proof
+        .save(dir.join(PROOF_FILE))
+        .map_err(|err| eyre::eyre!("failed to save the proof: {}", err))?;
+    std::fs::write(dir.join(PUBLIC_VALUES_FILE), proof.public_values.as_slice())?;
+    bincode::serialize_into(File::create(dir.join(VKEY_FILE))?, vk)?;
+    serde_json::to_writer_pretty(File::create(dir.join(METADATA_FILE))?, metadata)?;
+
+    Ok(())
+}
+
+/// Loads a proof and its metadata from a directory written by [save_proof].
+pub fn load_proof(dir: &Path) -> eyre::Result<(SP1ProofWithPublicValues, ProofMetadata)> {
+    let proof = SP1ProofWithPublicValues::load(dir.join(PROOF_FILE))
+        .map_err(|err| eyre::eyre!("failed to load the proof: {}", err))?;
+    let metadata = serde_json::from_reader(File::open(dir.join(METADATA_FILE))?)?;
+
+    Ok((proof, metadata))
+}
+
+/// Loads a verifying key, either from a file written by [save_proof] or from a proof directory.
+pub fn load_vkey(path: &Path) -> eyre::Result<SP1VerifyingKey> {
+    let path = if path.is_dir() { path.join(VKEY_FILE) } else { path.to_path_buf() };
+    Ok(bincode::deserialize_from(File::open(path)?)?)
+}
@@ -0,0 +1,156 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::Arc,
+};
+
+use futures::{stream, StreamExt};
+use sp1_sdk::ProverClient;
+use tokio::runtime::Handle;
+
+use crate::{
+    elf,
+    execute::{execute_and_report, record_execution_failure, ExecutionArgs, ExecutionReportData},
+    input::InputSource,
+    report::read_csv_report,
+};
+
+/// Executes every block of an inclusive range, recording the outcome of each block in the report.
+///
+/// Blocks already recorded in the report for the same chain are skipped, so that an interrupted
+/// run can be resumed. Inputs are fetched up to `parallelism` blocks ahead, while blocks are
+/// executed one at a time, in order.
+pub async fn run_range(
+    source: InputSource,
+    start_block: u64,
+    end_block: u64,
+    parallelism: usize,
+    retry_failures: bool,
+    execution: &ExecutionArgs,
+) -> eyre::Result<()> {
+    let chain_id = source.chain_id();
+
+    let report_path = &execution.report.report_path;
+    let done = read_csv_report(report_path)?
+        .into_iter()
+        .filter(|row| row.chain_id == chain_id && (row.success || !retry_failures))
+        .map(|row| row.block_number)
+        .collect::<HashSet<_>>();
+    let blocks = (start_block..=end_block)
+        .filter(|block_number| !done.contains(block_number))
+        .collect::<Vec<_>>();
+
+    let mut summary = RangeSummary {
+        skipped: (end_block - start_block + 1) - blocks.len() as u64,
+        ..Default::default()
+    };
+    if summary.skipped > 0 {
+        println!("skipping {} blocks already in {}", summary.skipped, report_path.display());
+    }
+
+    // The host executor isn't `Send`, so each input is built on a blocking thread of its own.
+    let source = Arc::new(source);
+    let handle = Handle::current();
+    let mut inputs = stream::iter(blocks)
+        .map(|block_number| {
+            let source = source.clone();
+            let handle = handle.clone();
+            let task = tokio::task::spawn_blocking(move || {
+                handle.block_on(source.load_input(block_number))
+            });
+            async move {
+                let client_input = match task.await {
+                    Ok(client_input) => client_input,
+                    Err(err) => Err(err.into()),
+                };
+                (block_number, client_input)
+            }
+        })
+        .buffered(parallelism.max(1));
+
+    let mut sinks = execution.report.sinks()?;
+    let client = ProverClient::new();
+    while let Some((block_number, client_input)) = inputs.next().await {
+        println!("\nExecuting block {}", block_number);
+
+        let result = match client_input {
+            Ok(client_input) => tokio::task::block_in_place(|| {
+                execute_and_report(
+                    &client,
+                    source.variant,
+                    elf(source.variant),
+                    client_input,
+                    execution,
+                    &mut sinks,
+                )
+            })
+            .map_err(|err| ("execute", err)),
+            Err(err) => Err(("fetch", err)),
+        };
+
+        match result {
+            Ok(report_data) => summary.record_success(&report_data),
+            Err((stage, err)) => {
+                tracing::error!("failed to {} block {}: {:#}", stage, block_number, err);
+                record_execution_failure(chain_id, block_number, &err, &mut sinks)?;
+                summary.record_failure(stage, &err);
+            }
+        }
+    }
+
+    summary.print();
+    let failed = summary.failures.values().sum::<usize>();
+    if failed > 0 {
+        eyre::bail!("{} blocks failed, see {}", failed, report_path.display());
+    }
+
+    Ok(())
+}
+
+/// Aggregate statistics over the blocks executed by a range run.
+#[derive(Debug, Default)]
+struct RangeSummary {
+    skipped: u64,
+    succeeded: u64,
+    gas_used: u64,
+    number_cycles: u64,
+    /// The number of failed blocks, by stage and kind of error.
+    failures: BTreeMap<String, usize>,
+}
+
+impl RangeSummary {
+    fn record_success(&mut self, report_data: &ExecutionReportData) {
+        self.succeeded += 1;
+        self.gas_used += report_data.gas_used;
+        self.number_cycles += report_data.number_cycles;
+    }
+
+    fn record_failure(&mut self, stage: &str, err: &eyre::Report) {
+        // Errors are grouped by the message of their root cause, without the details following
+        // the first colon, e.g. block hashes or addresses.
+        let root_cause = err.root_cause().to_string();
+        let kind = root_cause.split(':').next().unwrap_or_default().trim();
+        *self.failures.entry(format!("{}: {}", stage, kind)).or_default() += 1;
+    }
+
+    fn print(&self) {
+        let failed = self.failures.values().sum::<usize>();
+        println!("\nRange summary:");
+        println!("  succeeded: {}", self.succeeded);
+        println!("  failed:    {}", failed);
+        println!("  skipped:   {}", self.skipped);
+        println!("  gas used:  {}", self.gas_used);
+        if self.number_cycles > 0 {
+            println!("  cycles:    {}", self.number_cycles);
+            println!(
+                "  cycles per gas: {:.2}",
+                self.number_cycles as f64 / self.gas_used.max(1) as f64
+            );
+        }
+        if failed > 0 {
+            println!("  failures by error type:");
+            for (kind, count) in &self.failures {
+                println!("    {:>6}  {}", count, kind);
+            }
+        }
+    }
+}
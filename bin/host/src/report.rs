@@ -0,0 +1,310 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    fs::OpenOptions,
+    io::{BufWriter, Read, Write},
+    net::{SocketAddr, TcpListener},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use clap::Parser;
+use csv::{ReaderBuilder, WriterBuilder};
+use reth_primitives::{Address, B256};
+use serde::Serialize;
+
+use crate::execute::ExecutionReportData;
+
+/// The arguments for configuring where execution reports are written to.
+#[derive(Debug, Clone, Parser)]
+pub struct ReportArgs {
+    /// The path to the CSV file containing the execution data.
+    #[clap(long, default_value = "report.csv")]
+    pub report_path: PathBuf,
+    /// Optional path to a file the execution data is appended to as JSON lines, including the
+    /// cycles of every cycle tracker span and the count of every syscall.
+    #[clap(long)]
+    pub json_report_path: Option<PathBuf>,
+    /// Optional path to a file the metrics of the last executed block are written to in the
+    /// Prometheus text exposition format, e.g. for the textfile collector of node_exporter.
+    #[clap(long)]
+    pub prometheus_path: Option<PathBuf>,
+    /// Optional address to serve the metrics of the last executed block on, in the Prometheus
+    /// text exposition format.
+    #[clap(long)]
+    pub prometheus_addr: Option<SocketAddr>,
+}
+
+impl ReportArgs {
+    /// Creates the sinks execution reports are recorded to.
+    pub fn sinks(&self) -> eyre::Result<ReportSinks> {
+        let mut sinks: Vec<Box<dyn ReportSink>> =
+            vec![Box::new(CsvSink { path: self.report_path.clone() })];
+        if let Some(path) = &self.json_report_path {
+            sinks.push(Box::new(JsonLinesSink { path: path.clone() }));
+        }
+        if self.prometheus_path.is_some() || self.prometheus_addr.is_some() {
+            sinks.push(Box::new(PrometheusSink::new(
+                self.prometheus_path.clone(),
+                self.prometheus_addr,
+            )?));
+        }
+
+        Ok(ReportSinks { sinks })
+    }
+}
+
+/// The execution report of a block, as recorded by the report sinks.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BlockReport {
+    #[serde(flatten)]
+    pub data: ExecutionReportData,
+    /// The number of cycles spent in each cycle tracker span.
+    pub cycle_tracker: BTreeMap<String, u64>,
+    /// The number of calls to each syscall.
+    pub syscall_counts: BTreeMap<String, u64>,
+    /// The gas used and cycles spent by each transaction, if transactions were profiled.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub transactions: Vec<TxReport>,
+}
+
+/// The gas used and cycles spent by a transaction of a block.
+#[derive(Debug, Clone, Serialize)]
+pub struct TxReport {
+    pub index: usize,
+    pub hash: B256,
+    /// The top-level contract called, or `None` for contract creations.
+    pub to: Option<Address>,
+    pub gas_used: u64,
+    pub cycles: u64,
+}
+
+/// A destination execution reports are recorded to.
+pub trait ReportSink {
+    /// Records the execution report of a block.
+    fn record(&mut self, report: &BlockReport) -> eyre::Result<()>;
+}
+
+/// The set of sinks execution reports are recorded to.
+pub struct ReportSinks {
+    sinks: Vec<Box<dyn ReportSink>>,
+}
+
+impl ReportSink for ReportSinks {
+    fn record(&mut self, report: &BlockReport) -> eyre::Result<()> {
+        for sink in &mut self.sinks {
+            sink.record(report)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Appends the fixed set of columns of [ExecutionReportData] to a CSV file.
+struct CsvSink {
+    path: PathBuf,
+}
+
+impl ReportSink for CsvSink {
+    fn record(&mut self, report: &BlockReport) -> eyre::Result<()> {
+        // Open the file for appending or create it if it doesn't exist
+        let file = OpenOptions::new().append(true).create(true).open(&self.path)?;
+
+        // Check if the file is empty
+        let file_is_empty = file.metadata()?.len() == 0;
+
+        let mut writer = WriterBuilder::new().has_headers(file_is_empty).from_writer(file);
+        writer.serialize(&report.data)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Reads back the rows of the CSV report, if it exists.
+pub fn read_csv_report(path: &Path) -> eyre::Result<Vec<ExecutionReportData>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut reader = ReaderBuilder::new().from_path(path)?;
+    let rows = reader.deserialize().collect::<Result<_, _>>()?;
+
+    Ok(rows)
+}
+
+/// Appends whole reports to a file, one JSON object per line.
+struct JsonLinesSink {
+    path: PathBuf,
+}
+
+impl ReportSink for JsonLinesSink {
+    fn record(&mut self, report: &BlockReport) -> eyre::Result<()> {
+        let file = OpenOptions::new().append(true).create(true).open(&self.path)?;
+
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer(&mut writer, report)?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Exposes the metrics of the last executed block in the Prometheus text exposition format, to a
+/// file and over HTTP.
+struct PrometheusSink {
+    path: Option<PathBuf>,
+    metrics: Arc<Mutex<String>>,
+    executed_blocks: u64,
+    failed_blocks: u64,
+    last_executed: Option<BlockReport>,
+}
+
+impl PrometheusSink {
+    fn new(path: Option<PathBuf>, addr: Option<SocketAddr>) -> eyre::Result<Self> {
+        let metrics = Arc::new(Mutex::new(String::new()));
+        if let Some(addr) = addr {
+            let listener = TcpListener::bind(addr)?;
+            let metrics = metrics.clone();
+            std::thread::spawn(move || serve_metrics(listener, metrics));
+        }
+
+        Ok(Self { path, metrics, executed_blocks: 0, failed_blocks: 0, last_executed: None })
+    }
+
+    fn render(&self, chain_id: u64) -> String {
+        let chain = format!("chain_id=\"{}\"", chain_id);
+        let mut out = String::new();
+
+        let mut metric = |name: &str, kind: &str, help: &str, samples: &[(String, u64)]| {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} {}", name, kind);
+            for (labels, value) in samples {
+                let _ = writeln!(out, "{}{{{}}} {}", name, labels, value);
+            }
+        };
+
+        metric(
+            "rsp_blocks_executed_total",
+            "counter",
+            "The number of blocks executed successfully.",
+            &[(chain.clone(), self.executed_blocks)],
+        );
+        metric(
+            "rsp_blocks_failed_total",
+            "counter",
+            "The number of blocks which failed to execute.",
+            &[(chain.clone(), self.failed_blocks)],
+        );
+        let Some(report) = &self.last_executed else {
+            return out;
+        };
+        let data = &report.data;
+
+        metric(
+            "rsp_block_number",
+            "gauge",
+            "The number of the last executed block.",
+            &[(chain.clone(), data.block_number)],
+        );
+        metric(
+            "rsp_block_gas_used",
+            "gauge",
+            "The gas used by the last executed block.",
+            &[(chain.clone(), data.gas_used)],
+        );
+        metric(
+            "rsp_block_tx_count",
+            "gauge",
+            "The number of transactions of the last executed block.",
+            &[(chain.clone(), data.tx_count as u64)],
+        );
+        metric(
+            "rsp_block_cycles",
+            "gauge",
+            "The number of cycles spent executing the last block.",
+            &[(chain.clone(), data.number_cycles)],
+        );
+        metric(
+            "rsp_block_syscalls",
+            "gauge",
+            "The number of syscalls made executing the last block.",
+            &[(chain.clone(), data.number_syscalls)],
+        );
+        metric(
+            "rsp_block_span_cycles",
+            "gauge",
+            "The number of cycles spent in each cycle tracker span of the last block.",
+            // Transaction and contract spans are left out, as they would make new series for every
+            // block and every called contract.
+            &report
+                .cycle_tracker
+                .iter()
+                .filter(|(span, _)| !span.starts_with("tx-") && !span.starts_with("contract-"))
+                .map(|(span, cycles)| (format!("{},span=\"{}\"", chain, span), *cycles))
+                .collect::<Vec<_>>(),
+        );
+        metric(
+            "rsp_block_syscall_count",
+            "gauge",
+            "The number of calls to each syscall made executing the last block.",
+            &report
+                .syscall_counts
+                .iter()
+                .map(|(syscall, count)| (format!("{},syscall=\"{}\"", chain, syscall), *count))
+                .collect::<Vec<_>>(),
+        );
+
+        out
+    }
+}
+
+impl ReportSink for PrometheusSink {
+    fn record(&mut self, report: &BlockReport) -> eyre::Result<()> {
+        if report.data.success {
+            self.executed_blocks += 1;
+        } else {
+            self.failed_blocks += 1;
+        }
+
+        // Failed blocks only update the counters, keeping the metrics of the last executed block.
+        if report.data.success {
+            self.last_executed = Some(report.clone());
+        }
+        let mut metrics = self.metrics.lock().unwrap();
+        *metrics = self.render(report.data.chain_id);
+
+        if let Some(path) = &self.path {
+            // Write atomically, so that collectors never read a partial file.
+            let tmp_path = path.with_extension("tmp");
+            std::fs::write(&tmp_path, metrics.as_bytes())?;
+            std::fs::rename(tmp_path, path)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Answers every HTTP request with the current metrics.
+fn serve_metrics(listener: TcpListener, metrics: Arc<Mutex<String>>) {
+    for stream in listener.incoming() {
+        let Ok(mut stream) = stream else {
+            continue;
+        };
+
+        // The request itself doesn't matter, only its first chunk is consumed.
+        let mut request = [0u8; 1024];
+        let _ = stream.read(&mut request);
+
+        let body = metrics.lock().unwrap().clone();
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        if let Err(err) = stream.write_all(response.as_bytes()) {
+            tracing::warn!("failed to serve metrics: {}", err);
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use std::path::Path;
+
+use alloy_rlp::Encodable;
+use rsp_client_executor::io::ClientExecutorInput;
+use rsp_mpt::TrieStats;
+
+use crate::input_cache::read_input;
+
+/// Checks, without any RPC, that a cached input is consistent with its own block header, and
+/// prints a summary of the composition of its witness.
+///
+/// The parent state must match the state root of the parent header, the ancestor headers must form
+/// a chain of consecutive blocks up to the current one, every account must have its bytecode and
+/// every requested account and storage slot must resolve in the tries.
+pub fn verify_input(path: &Path) -> eyre::Result<()> {
+    let (header, client_input) = read_input(path)?;
+    println!(
+        "input: chain_id={}, block_number={}, block_hash={}, rsp_version={}, compressed={}",
+        header.chain_id,
+        header.block_number,
+        header.block_hash,
+        header.rsp_version,
+        header.compressed
+    );
+
+    print_witness_summary(&client_input);
+
+    client_input.witness_db().map_err(|err| eyre::eyre!("invalid witness: {}", err))?;
+    println!("success: the witness is consistent with the block header");
+
+    Ok(())
+}
+
+fn print_witness_summary(client_input: &ClientExecutorInput) {
+    let state = &client_input.parent_state;
+    let state_trie = state.state_trie.stats();
+    let mut storage_tries = TrieStats::default();
+    for storage_trie in state.storage_tries.values() {
+        storage_tries += storage_trie.stats();
+    }
+
+    let accounts = client_input.state_requests.len();
+    let slots = client_input.state_requests.values().map(Vec::len).sum::<usize>();
+    let bytecode_bytes = client_input.bytecodes.iter().map(|code| code.len()).sum::<usize>();
+    let header_bytes = client_input.ancestor_headers.iter().map(|header| header.length()).sum();
+    let block_bytes = client_input.current_block.length();
+
+    println!("\nWitness summary:");
+    println!("  requested accounts:  {}", accounts);
+    println!("  requested slots:     {}", slots);
+    print_category("state trie", state_trie.nodes, state_trie.bytes);
+    println!("    leaves: {}, unresolved: {}", state_trie.leaves, state_trie.digests);
+    print_category(
+        &format!("storage tries ({})", state.storage_tries.len()),
+        storage_tries.nodes,
+        storage_tries.bytes,
+    );
+    println!("    leaves: {}, unresolved: {}", storage_tries.leaves, storage_tries.digests);
+    print_category("bytecodes", client_input.bytecodes.len(), bytecode_bytes);
+    print_category("ancestor headers", client_input.ancestor_headers.len(), header_bytes);
+    print_category("block (transactions)", client_input.current_block.body.len(), block_bytes);
+    println!();
+}
+
+fn print_category(name: &str, count: usize, bytes: usize) {
+    println!("  {:<24} {:>8} items {:>12} bytes", format!("{}:", name), count, bytes);
+}
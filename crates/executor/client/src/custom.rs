@@ -1,85 +1,189 @@
-//! A cunstom EVM configuration for annotated precompiles.
+//! A cunstom EVM configuration for annotated precompiles and transactions.
 //!
 //! Originally from: https://github.com/paradigmxyz/alphanet/blob/main/crates/node/src/evm.rs.
 //!
 //! The [CustomEvmConfig] type implements the [ConfigureEvm] and [ConfigureEvmEnv] traits,
 //! configuring the custom CustomEvmConfig precompiles and instructions.
 
-use crate::ChainVariant;
+use crate::{
+    io::{PrecompileAnnotation, ProfilingOptions},
+    ChainVariant,
+};
+use alloy_primitives::address;
 use reth_chainspec::ChainSpec;
 use reth_evm::{ConfigureEvm, ConfigureEvmEnv};
 use reth_evm_ethereum::EthEvmConfig;
 use reth_evm_optimism::OptimismEvmConfig;
 use reth_primitives::{
-    revm_primitives::{CfgEnvWithHandlerCfg, TxEnv},
-    Address, Bytes, Header, TransactionSigned, U256,
+    revm_primitives::{CfgEnvWithHandlerCfg, SpecId, TxEnv, TxKind},
+    Address, Block, Bytes, Header, TransactionSigned, B256, U256,
 };
 use reth_revm::{
-    handler::register::EvmHandler, precompile::PrecompileSpecId, primitives::Env,
-    ContextPrecompiles, Database, Evm, EvmBuilder,
+    handler::register::EvmHandler, inspector_handle_register, precompile::PrecompileSpecId,
+    primitives::Env, ContextPrecompiles, Database, Evm, EvmBuilder, GetInspector,
 };
 use revm::precompile::{
-    bn128, kzg_point_evaluation, secp256k1, Precompile, PrecompileResult, PrecompileWithAddress,
+    secp256r1, Precompile, PrecompileResult, PrecompileWithAddress, Precompiles, StatefulPrecompile,
 };
-use std::sync::Arc;
-
-/// Create an annotated precompile that tracks the cycle count of a precompile.
-/// This is useful for tracking how many cycles in total are consumed by calls to a given
-/// precompile.
-macro_rules! create_annotated_precompile {
-    ($precompile:expr, $name:expr) => {
-        PrecompileWithAddress(
-            $precompile.0,
-            Precompile::Standard(|input: &Bytes, gas_limit: u64| -> PrecompileResult {
-                let precompile = $precompile.precompile();
-                match precompile {
-                    Precompile::Standard(precompile) => {
-                        println!(concat!("cycle-tracker-report-start: precompile-", $name));
-                        let result = precompile(input, gas_limit);
-                        println!(concat!("cycle-tracker-report-end: precompile-", $name));
-                        result
-                    }
-                    _ => panic!("Annotated precompile must be a standard precompile."),
-                }
-            }),
-        )
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+/// The caller of the system contract calls made around the transactions of a block, e.g. to the
+/// EIP-4788 beacon roots contract, which are not annotated as transactions.
+const SYSTEM_ADDRESS: Address = address!("fffffffffffffffffffffffffffffffffffffffe");
+
+/// A precompile wrapped with cycle tracker spans, to track how many cycles are consumed by calls
+/// to it.
+struct AnnotatedPrecompile {
+    /// The name of the aggregate span, `precompile-<name>`.
+    span: String,
+    /// The name of the per-call span, `precompile-<name>-call`, if calls are annotated one by one.
+    call_span: Option<String>,
+    precompile: Precompile,
+}
+
+impl StatefulPrecompile for AnnotatedPrecompile {
+    fn call(&self, input: &Bytes, gas_limit: u64, env: &Env) -> PrecompileResult {
+        println!("cycle-tracker-report-start: {}", self.span);
+        if let Some(call_span) = &self.call_span {
+            println!("cycle-tracker-start: {}", call_span);
+        }
+
+        let result = call_precompile(&self.precompile, input, gas_limit, env);
+
+        if let Some(call_span) = &self.call_span {
+            println!("cycle-tracker-end: {}", call_span);
+        }
+        println!("cycle-tracker-report-end: {}", self.span);
+        result
+    }
+}
+
+/// Calls a precompile which doesn't mutate its state.
+pub(crate) fn call_precompile(
+    precompile: &Precompile,
+    input: &Bytes,
+    gas_limit: u64,
+    env: &Env,
+) -> PrecompileResult {
+    match precompile {
+        Precompile::Standard(precompile) => precompile(input, gas_limit),
+        Precompile::Env(precompile) => precompile(input, gas_limit, env),
+        Precompile::Stateful(precompile) => precompile.call(input, gas_limit, env),
+        Precompile::StatefulMut(_) => panic!("Precompile must not be mutable."),
+    }
+}
+
+/// Returns the name of the precompile at the given address, as used in cycle tracker spans.
+fn precompile_name(address: &Address) -> String {
+    let bytes = address.as_slice();
+    let name = if bytes[..18].iter().all(|byte| *byte == 0) {
+        match u16::from_be_bytes([bytes[18], bytes[19]]) {
+            0x01 => Some("ecrecover"),
+            0x02 => Some("sha256"),
+            0x03 => Some("ripemd160"),
+            0x04 => Some("identity"),
+            0x05 => Some("modexp"),
+            0x06 => Some("bn-add"),
+            0x07 => Some("bn-mul"),
+            0x08 => Some("bn-pair"),
+            0x09 => Some("blake2f"),
+            0x0a => Some("kzg-point-evaluation"),
+            0x100 => Some("p256-verify"),
+            _ => None,
+        }
+    } else {
+        None
     };
+
+    match name {
+        Some(name) => name.to_string(),
+        None => address.to_string(),
+    }
 }
 
-// An annotated version of the KZG point evaluation precompile. Because this is a stateful
-// precompile we cannot use the `create_annotated_precompile` macro
-pub(crate) const ANNOTATED_KZG_PROOF: PrecompileWithAddress = PrecompileWithAddress(
-    kzg_point_evaluation::POINT_EVALUATION.0,
-    Precompile::Env(|input: &Bytes, gas_limit: u64, env: &Env| -> PrecompileResult {
-        let precompile = kzg_point_evaluation::POINT_EVALUATION.precompile();
-        match precompile {
-            Precompile::Env(precompile) => {
-                println!(concat!(
-                    "cycle-tracker-report-start: precompile-",
-                    "kzg-point-evaluation"
-                ));
-                let result = precompile(input, gas_limit, env);
-                println!(concat!("cycle-tracker-report-end: precompile-", "kzg-point-evaluation"));
-                result
-            }
-            _ => panic!("Annotated precompile must be a env precompile."),
+/// Returns whether the RIP-7212 P256VERIFY precompile is active for a chain variant at a spec.
+///
+/// OP-stack chains activated it at address `0x100` with [OptimismHardfork::Fjord], which
+/// [OptimismEvmConfig] maps to [SpecId::FJORD]. Linea and Ethereum chains don't have it.
+///
+/// [OptimismHardfork::Fjord]: reth_chainspec::OptimismHardfork::Fjord
+fn is_p256_verify_active(variant: ChainVariant, spec_id: SpecId) -> bool {
+    match variant {
+        ChainVariant::Optimism | ChainVariant::Base | ChainVariant::OpSepolia => {
+            spec_id.is_enabled_in(SpecId::FJORD)
         }
-    }),
-);
-
-pub(crate) const ANNOTATED_ECRECOVER: PrecompileWithAddress =
-    create_annotated_precompile!(secp256k1::ECRECOVER, "ecrecover");
-pub(crate) const ANNOTATED_BN_ADD: PrecompileWithAddress =
-    create_annotated_precompile!(bn128::add::ISTANBUL, "bn-add");
-pub(crate) const ANNOTATED_BN_MUL: PrecompileWithAddress =
-    create_annotated_precompile!(bn128::mul::ISTANBUL, "bn-mul");
-pub(crate) const ANNOTATED_BN_PAIR: PrecompileWithAddress =
-    create_annotated_precompile!(bn128::pair::ISTANBUL, "bn-pair");
+        ChainVariant::Ethereum |
+        ChainVariant::Sepolia |
+        ChainVariant::Holesky |
+        ChainVariant::Linea => false,
+    }
+}
+
+/// Returns the reference precompiles of a chain variant at a spec, as implemented by revm.
+pub(crate) fn variant_precompiles(variant: ChainVariant, spec_id: SpecId) -> Precompiles {
+    let mut precompiles = Precompiles::new(PrecompileSpecId::from_spec_id(spec_id)).clone();
+    if is_p256_verify_active(variant, spec_id) {
+        precompiles.extend([secp256r1::P256VERIFY]);
+    }
+    precompiles
+}
+
+/// Replaces the precompiles of a set with the overrides at the same addresses.
+///
+/// Overrides of precompiles which are not part of the set are ignored, so that an override never
+/// activates a precompile ahead of its hardfork.
+fn apply_precompile_overrides(precompiles: &mut Precompiles, overrides: &[PrecompileWithAddress]) {
+    let overrides = overrides
+        .iter()
+        .filter(|precompile| precompiles.contains(&precompile.0))
+        .cloned()
+        .collect::<Vec<_>>();
+    precompiles.extend(overrides);
+}
+
+/// Annotates every precompile of a set according to the annotation mode.
+fn annotate_precompiles(
+    precompiles: &Precompiles,
+    annotation: PrecompileAnnotation,
+) -> Vec<PrecompileWithAddress> {
+    precompiles
+        .inner()
+        .iter()
+        .map(|(address, precompile)| {
+            let precompile = match annotation {
+                PrecompileAnnotation::None => precompile.clone(),
+                PrecompileAnnotation::Aggregate | PrecompileAnnotation::PerCall => {
+                    let name = precompile_name(address);
+                    Precompile::Stateful(Arc::new(AnnotatedPrecompile {
+                        span: format!("precompile-{}", name),
+                        call_span: (annotation == PrecompileAnnotation::PerCall)
+                            .then(|| format!("precompile-{}-call", name)),
+                        precompile: precompile.clone(),
+                    }))
+                }
+            };
+            PrecompileWithAddress(*address, precompile)
+        })
+        .collect()
+}
 
 /// Custom EVM configuration
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 #[non_exhaustive]
-pub struct CustomEvmConfig(pub ChainVariant);
+pub struct CustomEvmConfig {
+    /// The chain variant the EVM is configured for.
+    pub variant: ChainVariant,
+    /// How calls to precompiles are annotated with cycle tracker spans.
+    precompile_annotation: PrecompileAnnotation,
+    /// The precompiles replacing the reference implementations at the same addresses.
+    precompile_overrides: Arc<[PrecompileWithAddress]>,
+    /// The hashes of the transactions of the block, if each of them is to be annotated with a
+    /// cycle tracker span.
+    tx_hashes: Option<Arc<[B256]>>,
+}
 
 impl CustomEvmConfig {
     /// Sets the precompiles to the EVM handler
@@ -87,59 +191,208 @@ impl CustomEvmConfig {
     /// This will be invoked when the EVM is created via [ConfigureEvm::evm] or
     /// [ConfigureEvm::evm_with_inspector]
     ///
-    /// This will use the default mainnet precompiles and add additional precompiles.
-    fn set_precompiles<EXT, DB>(handler: &mut EvmHandler<'_, EXT, DB>)
-    where
+    /// This will use the default mainnet precompiles of the spec, along with the precompiles
+    /// specific to the chain variant, replaced by the overrides and annotated as requested.
+    fn set_precompiles<EXT, DB>(
+        handler: &mut EvmHandler<'_, EXT, DB>,
+        variant: ChainVariant,
+        overrides: &[PrecompileWithAddress],
+        annotation: PrecompileAnnotation,
+    ) where
         DB: Database,
     {
         // first we need the evm spec id, which determines the precompiles
         let spec_id = handler.cfg.spec_id;
+        let precompile_spec_id = PrecompileSpecId::from_spec_id(spec_id);
+        let mut precompiles = variant_precompiles(variant, spec_id);
+        apply_precompile_overrides(&mut precompiles, overrides);
+        let annotated = annotate_precompiles(&precompiles, annotation);
         // install the precompiles
         handler.pre_execution.load_precompiles = Arc::new(move || {
             let mut loaded_precompiles: ContextPrecompiles<DB> =
-                ContextPrecompiles::new(PrecompileSpecId::from_spec_id(spec_id));
-            loaded_precompiles.extend(vec![
-                ANNOTATED_ECRECOVER,
-                ANNOTATED_BN_ADD,
-                ANNOTATED_BN_MUL,
-                ANNOTATED_BN_PAIR,
-                ANNOTATED_KZG_PROOF,
-            ]);
-
+                ContextPrecompiles::new(precompile_spec_id);
+            loaded_precompiles.extend(annotated.clone());
             loaded_precompiles
         });
     }
 
+    /// Annotates every transaction executed by the EVM with a cycle tracker span named after its
+    /// index and hash, nested in a span named after the top-level contract it calls.
+    ///
+    /// The index of a transaction is the number of transactions executed before it by the same
+    /// EVM, which matches its index in the block as system contract calls are skipped.
+    fn set_tx_spans<EXT, DB>(
+        handler: &mut EvmHandler<'_, EXT, DB>,
+        tx_hashes: Arc<[B256]>,
+        tx_index: Arc<AtomicUsize>,
+    ) where
+        DB: Database,
+    {
+        let load_accounts = handler.pre_execution.load_accounts.clone();
+        let start_hashes = tx_hashes.clone();
+        let start_index = tx_index.clone();
+        handler.pre_execution.load_accounts = Arc::new(move |ctx| {
+            if ctx.evm.env.tx.caller != SYSTEM_ADDRESS {
+                let index = start_index.fetch_add(1, Ordering::Relaxed);
+                let (tx_span, contract_span) = tx_span_names(&ctx.evm.env.tx, &start_hashes, index);
+                println!("cycle-tracker-report-start: {}", contract_span);
+                println!("cycle-tracker-report-start: {}", tx_span);
+            }
+            load_accounts(ctx)
+        });
+
+        let end = handler.post_execution.end.clone();
+        handler.post_execution.end = Arc::new(move |ctx, result| {
+            let result = end(ctx, result);
+            if ctx.evm.env.tx.caller != SYSTEM_ADDRESS {
+                let index = tx_index.load(Ordering::Relaxed).saturating_sub(1);
+                let (tx_span, contract_span) = tx_span_names(&ctx.evm.env.tx, &tx_hashes, index);
+                println!("cycle-tracker-report-end: {}", tx_span);
+                println!("cycle-tracker-report-end: {}", contract_span);
+            }
+            result
+        });
+    }
+
+    /// Installs the precompiles and, if enabled, the transaction spans to the EVM handler.
+    ///
+    /// The handler is registered again whenever the spec changes, so the index of the next
+    /// transaction is shared by all the registrations.
+    fn register_handlers<EXT, DB>(
+        &self,
+        handler: &mut EvmHandler<'_, EXT, DB>,
+        tx_index: &Arc<AtomicUsize>,
+    ) where
+        DB: Database,
+    {
+        Self::set_precompiles(
+            handler,
+            self.variant,
+            &self.precompile_overrides,
+            self.precompile_annotation,
+        );
+        if let Some(tx_hashes) = &self.tx_hashes {
+            Self::set_tx_spans(handler, tx_hashes.clone(), tx_index.clone());
+        }
+    }
+
     pub fn from_variant(variant: ChainVariant) -> Self {
-        Self(variant)
+        Self {
+            variant,
+            precompile_annotation: PrecompileAnnotation::default(),
+            precompile_overrides: Arc::new([]),
+            tx_hashes: None,
+        }
     }
+
+    /// Replaces the reference implementations of the precompiles at the addresses of the
+    /// overrides, e.g. with implementations accelerated in the zkVM.
+    ///
+    /// Overrides of precompiles which are not active at the spec of a block are ignored.
+    pub fn with_precompile_overrides(
+        mut self,
+        overrides: impl IntoIterator<Item = PrecompileWithAddress>,
+    ) -> Self {
+        self.precompile_overrides = overrides.into_iter().collect();
+        self
+    }
+
+    /// Enables the cycle tracker spans requested by the profiling options for the execution of
+    /// the given block.
+    pub fn with_profiling(mut self, options: ProfilingOptions, block: &Block) -> Self {
+        self.precompile_annotation = options.precompiles;
+        if options.transactions {
+            self.tx_hashes = Some(block.body.iter().map(|tx| tx.hash()).collect());
+        }
+        self
+    }
+}
+
+/// Returns the names of the cycle tracker spans of a transaction and of the contract it calls.
+fn tx_span_names(tx: &TxEnv, tx_hashes: &[B256], index: usize) -> (String, String) {
+    let tx_span = match tx_hashes.get(index) {
+        Some(hash) => format!("tx-{}-{}", index, hash),
+        None => format!("tx-{}", index),
+    };
+    let contract_span = match tx.transact_to {
+        TxKind::Call(address) => format!("contract-{}", address),
+        TxKind::Create => "contract-create".to_string(),
+    };
+
+    (tx_span, contract_span)
 }
 
 impl ConfigureEvm for CustomEvmConfig {
     type DefaultExternalContext<'a> = ();
 
     fn evm<DB: Database>(&self, db: DB) -> Evm<'_, Self::DefaultExternalContext<'_>, DB> {
-        match self.0 {
-            ChainVariant::Ethereum => {
+        let config = self.clone();
+        let tx_index = Arc::new(AtomicUsize::new(0));
+        let register_handlers = move |handler: &mut EvmHandler<'_, (), DB>| {
+            config.register_handlers(handler, &tx_index)
+        };
+        match self.variant {
+            ChainVariant::Ethereum | ChainVariant::Sepolia | ChainVariant::Holesky => {
                 EvmBuilder::default()
                     .with_db(db)
                     // add additional precompiles
-                    .append_handler_register(Self::set_precompiles)
+                    .append_handler_register_box(Box::new(register_handlers))
                     .build()
             }
-            ChainVariant::Optimism => {
+            ChainVariant::Optimism | ChainVariant::Base | ChainVariant::OpSepolia => {
                 EvmBuilder::default()
                     .with_db(db)
                     .optimism()
                     // add additional precompiles
-                    .append_handler_register(Self::set_precompiles)
+                    .append_handler_register_box(Box::new(register_handlers))
                     .build()
             }
             ChainVariant::Linea => {
                 EvmBuilder::default()
                     .with_db(db)
                     // add additional precompiles
-                    .append_handler_register(Self::set_precompiles)
+                    .append_handler_register_box(Box::new(register_handlers))
+                    .build()
+            }
+        }
+    }
+
+    fn evm_with_inspector<DB, I>(&self, db: DB, inspector: I) -> Evm<'_, I, DB>
+    where
+        DB: Database,
+        I: GetInspector<DB>,
+    {
+        let config = self.clone();
+        let tx_index = Arc::new(AtomicUsize::new(0));
+        let register_handlers =
+            move |handler: &mut EvmHandler<'_, I, DB>| config.register_handlers(handler, &tx_index);
+        match self.variant {
+            ChainVariant::Ethereum | ChainVariant::Sepolia | ChainVariant::Holesky => {
+                EvmBuilder::default()
+                    .with_db(db)
+                    .with_external_context(inspector)
+                    // add additional precompiles
+                    .append_handler_register_box(Box::new(register_handlers))
+                    .append_handler_register(inspector_handle_register)
+                    .build()
+            }
+            ChainVariant::Optimism | ChainVariant::Base | ChainVariant::OpSepolia => {
+                EvmBuilder::default()
+                    .with_db(db)
+                    .with_external_context(inspector)
+                    .optimism()
+                    // add additional precompiles
+                    .append_handler_register_box(Box::new(register_handlers))
+                    .append_handler_register(inspector_handle_register)
+                    .build()
+            }
+            ChainVariant::Linea => {
+                EvmBuilder::default()
+                    .with_db(db)
+                    .with_external_context(inspector)
+                    // add additional precompiles
+                    .append_handler_register_box(Box::new(register_handlers))
+                    .append_handler_register(inspector_handle_register)
                     .build()
             }
         }
@@ -150,11 +403,11 @@ impl ConfigureEvm for CustomEvmConfig {
 
 impl ConfigureEvmEnv for CustomEvmConfig {
     fn fill_tx_env(&self, tx_env: &mut TxEnv, transaction: &TransactionSigned, sender: Address) {
-        match self.0 {
-            ChainVariant::Ethereum => {
+        match self.variant {
+            ChainVariant::Ethereum | ChainVariant::Sepolia | ChainVariant::Holesky => {
                 EthEvmConfig::default().fill_tx_env(tx_env, transaction, sender)
             }
-            ChainVariant::Optimism => {
+            ChainVariant::Optimism | ChainVariant::Base | ChainVariant::OpSepolia => {
                 OptimismEvmConfig::default().fill_tx_env(tx_env, transaction, sender)
             }
             ChainVariant::Linea => EthEvmConfig::default().fill_tx_env(tx_env, transaction, sender),
@@ -168,16 +421,18 @@ impl ConfigureEvmEnv for CustomEvmConfig {
         header: &Header,
         total_difficulty: U256,
     ) {
-        match self.0 {
-            ChainVariant::Ethereum => {
+        match self.variant {
+            ChainVariant::Ethereum | ChainVariant::Sepolia | ChainVariant::Holesky => {
                 EthEvmConfig::default().fill_cfg_env(cfg_env, chain_spec, header, total_difficulty)
             }
-            ChainVariant::Optimism => OptimismEvmConfig::default().fill_cfg_env(
-                cfg_env,
-                chain_spec,
-                header,
-                total_difficulty,
-            ),
+            ChainVariant::Optimism | ChainVariant::Base | ChainVariant::OpSepolia => {
+                OptimismEvmConfig::default().fill_cfg_env(
+                    cfg_env,
+                    chain_spec,
+                    header,
+                    total_difficulty,
+                )
+            }
             ChainVariant::Linea => {
                 EthEvmConfig::default().fill_cfg_env(cfg_env, chain_spec, header, total_difficulty)
             }
@@ -191,11 +446,15 @@ impl ConfigureEvmEnv for CustomEvmConfig {
         contract: Address,
         data: Bytes,
     ) {
-        match self.0 {
-            ChainVariant::Ethereum => EthEvmConfig::default()
-                .fill_tx_env_system_contract_call(env, caller, contract, data),
-            ChainVariant::Optimism => OptimismEvmConfig::default()
-                .fill_tx_env_system_contract_call(env, caller, contract, data),
+        match self.variant {
+            ChainVariant::Ethereum | ChainVariant::Sepolia | ChainVariant::Holesky => {
+                EthEvmConfig::default()
+                    .fill_tx_env_system_contract_call(env, caller, contract, data)
+            }
+            ChainVariant::Optimism | ChainVariant::Base | ChainVariant::OpSepolia => {
+                OptimismEvmConfig::default()
+                    .fill_tx_env_system_contract_call(env, caller, contract, data)
+            }
             ChainVariant::Linea => EthEvmConfig::default()
                 .fill_tx_env_system_contract_call(env, caller, contract, data),
         }
@@ -233,4 +492,56 @@ mod tests {
 
         assert_eq!(cfg_env.chain_id, chain_spec.chain().id());
     }
+
+    #[test]
+    fn test_annotate_precompiles() {
+        let precompiles = Precompiles::new(PrecompileSpecId::CANCUN);
+
+        let annotated = annotate_precompiles(precompiles, PrecompileAnnotation::Aggregate);
+        assert_eq!(annotated.len(), precompiles.len());
+        for PrecompileWithAddress(address, precompile) in annotated {
+            assert!(matches!(precompile, Precompile::Stateful(_)));
+            assert_ne!(precompile_name(&address), address.to_string());
+        }
+
+        let annotated = annotate_precompiles(precompiles, PrecompileAnnotation::None);
+        assert!(annotated.iter().all(|p| !matches!(p.1, Precompile::Stateful(_))));
+    }
+
+    #[test]
+    fn test_p256_verify() {
+        use p256::ecdsa::{signature::hazmat::PrehashSigner, Signature, SigningKey};
+
+        let p256_verify = secp256r1::P256VERIFY.0;
+        assert!(!variant_precompiles(ChainVariant::Base, SpecId::ECOTONE).contains(&p256_verify));
+        assert!(!variant_precompiles(ChainVariant::Linea, SpecId::LATEST).contains(&p256_verify));
+        assert!(!variant_precompiles(ChainVariant::Ethereum, SpecId::LATEST).contains(&p256_verify));
+        assert_eq!(precompile_name(&p256_verify), "p256-verify");
+
+        // The input is the message hash, the `r` and `s` components of the signature, and the `x`
+        // and `y` coordinates of the public key.
+        let signing_key = SigningKey::from_slice(&[0x42; 32]).unwrap();
+        let hash = B256::repeat_byte(0x01);
+        let signature: Signature = signing_key.sign_prehash(hash.as_slice()).unwrap();
+        let public_key = signing_key.verifying_key().to_encoded_point(false);
+        let input = [hash.as_slice(), &signature.to_bytes(), &public_key.as_bytes()[1..]].concat();
+        let mut tampered_input = input.clone();
+        tampered_input[0] ^= 1;
+
+        let precompiles = variant_precompiles(ChainVariant::Base, SpecId::FJORD);
+        for annotation in [PrecompileAnnotation::None, PrecompileAnnotation::PerCall] {
+            let annotated = annotate_precompiles(&precompiles, annotation);
+            let PrecompileWithAddress(_, precompile) =
+                annotated.into_iter().find(|p| p.0 == p256_verify).unwrap();
+            let call = |input: &[u8]| {
+                call_precompile(&precompile, &input.to_vec().into(), 10_000, &Env::default())
+            };
+
+            let output = call(&input).unwrap();
+            assert_eq!(output.bytes.as_ref(), B256::with_last_byte(1).as_slice());
+            assert_eq!(output.gas_used, 3450);
+            assert!(call(&tampered_input).unwrap().bytes.is_empty());
+            assert!(call(&input[..159]).unwrap().bytes.is_empty());
+        }
+    }
 }
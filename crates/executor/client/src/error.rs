@@ -0,0 +1,74 @@
+use reth_errors::{BlockExecutionError, ConsensusError};
+use revm_primitives::{Address, B256, U256};
+use rsp_mpt::{MptError, StateError};
+use rsp_primitives::chain_spec::ChainSpecError;
+use rsp_witness_db::{WitnessDb, WitnessDbError};
+
+/// Errors that can occur when executing blocks in the [ClientExecutor](crate::ClientExecutor).
+///
+/// Variants prefixed with `Missing`, as well as [ClientError::ParentStateRootMismatch] and
+/// [ClientError::InvalidAncestorHeaders], indicate a malformed witness rather than an invalid
+/// block.
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("empty block range")]
+    EmptyBlockRange,
+    #[error("invalid chain spec: {0}")]
+    InvalidChainSpec(#[from] ChainSpecError),
+    /// The block is at or after the activation of a hardfork the execution engine doesn't
+    /// implement.
+    #[error("unsupported block: {0}")]
+    UnsupportedBlock(ChainSpecError),
+    #[error("parent state root mismatch: expected={expected}, actual={actual}")]
+    ParentStateRootMismatch { expected: B256, actual: B256 },
+    #[error("invalid ancestor headers: {0}")]
+    InvalidAncestorHeaders(&'static str),
+    #[error("mismatched state root: expected={expected}, actual={actual}")]
+    StateRootMismatch { expected: B256, actual: B256 },
+    #[error("missing account in witness: {0}")]
+    MissingAccount(Address),
+    #[error("missing storage slot in witness: address={0}, slot={1}")]
+    MissingSlot(Address, U256),
+    #[error("missing storage trie in witness: {0}")]
+    MissingStorageTrie(Address),
+    #[error("missing block hash in witness: {0}")]
+    MissingBlockHash(u64),
+    #[error("missing bytecode in witness: {0}")]
+    MissingBytecode(B256),
+    #[error("invalid clique seal: {0}")]
+    InvalidCliqueSeal(&'static str),
+    #[error("failed to recover senders")]
+    SenderRecovery,
+    /// Errors returned while executing the block. Failed reads from the witness database are
+    /// reported as the corresponding `Missing` variants instead.
+    #[error("block execution failed: {0}")]
+    BlockExecution(#[from] BlockExecutionError),
+    #[error("post-execution validation failed: {0}")]
+    PostExecutionValidation(#[from] ConsensusError),
+    #[error("failed to update state: {0}")]
+    State(#[from] StateError),
+    #[error("trie error: {0}")]
+    Trie(#[from] MptError),
+}
+
+impl From<WitnessDbError> for ClientError {
+    fn from(error: WitnessDbError) -> Self {
+        match error {
+            WitnessDbError::MissingAccount(address) => Self::MissingAccount(address),
+            WitnessDbError::MissingSlot(address, slot) => Self::MissingSlot(address, slot),
+            WitnessDbError::MissingBlockHash(number) => Self::MissingBlockHash(number),
+            WitnessDbError::MissingBytecode(code_hash) => Self::MissingBytecode(code_hash),
+        }
+    }
+}
+
+impl ClientError {
+    /// Replaces a block execution error caused by a failed read from the witness database, which
+    /// reth only reports as a string, with the typed error recorded by the database.
+    pub(crate) fn with_witness_error(self, witness_db: &WitnessDb) -> Self {
+        match (self, witness_db.take_error()) {
+            (Self::BlockExecution(_), Some(error)) => error.into(),
+            (error, _) => error,
+        }
+    }
+}
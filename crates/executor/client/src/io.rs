@@ -1,14 +1,17 @@
-use std::{collections::HashMap, iter::once};
+use std::{collections::HashMap, fmt, iter::once, str::FromStr};
 
-use eyre::Result;
+use alloy_sol_types::sol;
 use itertools::Itertools;
 use reth_primitives::{revm_primitives::AccountInfo, Address, Block, Header, B256, U256};
 use reth_trie::TrieAccount;
 use revm_primitives::{keccak256, Bytecode};
 use rsp_mpt::EthereumState;
+use rsp_primitives::chain_spec::ChainSpecConfig;
 use rsp_witness_db::WitnessDb;
 use serde::{Deserialize, Serialize};
 
+use crate::error::ClientError;
+
 /// The input for the client to execute a block and fully verify the STF (state transition
 /// function).
 ///
@@ -16,6 +19,8 @@ use serde::{Deserialize, Serialize};
 /// for the storage slots that were modified and accessed.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct ClientExecutorInput {
+    /// The chain spec under which the block is executed.
+    pub chain_spec: ChainSpecConfig,
     /// The current block (which will be executed inside the client).
     pub current_block: Block,
     /// The previous block headers starting from the most recent. There must be at least one header
@@ -37,7 +42,7 @@ impl ClientExecutorInput {
     }
 
     /// Creates a [`WitnessDb`].
-    pub fn witness_db(&self) -> Result<WitnessDb> {
+    pub fn witness_db(&self) -> Result<WitnessDb, ClientError> {
         <Self as WitnessInput>::witness_db(self)
     }
 }
@@ -69,6 +74,188 @@ impl WitnessInput for ClientExecutorInput {
     }
 }
 
+/// Options controlling the cycle tracker spans emitted while executing a block, read by the
+/// client programs after the [ClientExecutorInput].
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ProfilingOptions {
+    /// Whether to emit a cycle tracker span for every transaction, named `tx-<index>-<hash>`,
+    /// nested in a span for the top-level contract it calls, named `contract-<address>`.
+    pub transactions: bool,
+    /// How calls to precompiles are annotated.
+    pub precompiles: PrecompileAnnotation,
+}
+
+impl ProfilingOptions {
+    /// Options emitting no cycle tracker span at all, e.g. when proving or executing natively.
+    pub fn none() -> Self {
+        Self { transactions: false, precompiles: PrecompileAnnotation::None }
+    }
+}
+
+/// How calls to precompiles are annotated with cycle tracker spans.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum PrecompileAnnotation {
+    /// Precompiles are not annotated, saving the cycles spent printing the annotations.
+    None,
+    /// The cycles spent in each precompile are summed over the block, in a span named
+    /// `precompile-<name>`.
+    #[default]
+    Aggregate,
+    /// On top of the aggregate span, every call is annotated with a span named
+    /// `precompile-<name>-call`, whose cycles are logged by the zkVM executor.
+    PerCall,
+}
+
+impl fmt::Display for PrecompileAnnotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let annotation = match self {
+            Self::None => "none",
+            Self::Aggregate => "aggregate",
+            Self::PerCall => "per-call",
+        };
+        f.write_str(annotation)
+    }
+}
+
+impl FromStr for PrecompileAnnotation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "aggregate" => Ok(Self::Aggregate),
+            "per-call" => Ok(Self::PerCall),
+            _ => Err(format!("unknown precompile annotation: {}", s)),
+        }
+    }
+}
+
+/// The input for the client to execute a range of consecutive blocks against a single witness.
+///
+/// The state tries contain the nodes required by every block in the range, so that each block can
+/// be applied to the state produced by the previous one.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ClientExecutorRangeInput {
+    /// The chain spec under which the blocks are executed.
+    pub chain_spec: ChainSpecConfig,
+    /// The blocks to execute, in ascending order.
+    pub blocks: Vec<Block>,
+    /// The block headers preceding the first block, starting from the most recent. There must be
+    /// at least one header to provide the parent state root.
+    pub ancestor_headers: Vec<Header>,
+    /// Network state as of the parent of the first block.
+    pub parent_state: EthereumState,
+    /// Requests to account state and storage slots made throughout the range.
+    pub state_requests: HashMap<Address, Vec<U256>>,
+    /// Account bytecodes.
+    pub bytecodes: Vec<Bytecode>,
+}
+
+impl ClientExecutorRangeInput {
+    /// Gets the header of the parent of the first block.
+    #[inline(always)]
+    pub fn parent_header(&self) -> &Header {
+        &self.ancestor_headers[0]
+    }
+
+    /// Creates a [`WitnessDb`].
+    pub fn witness_db(&self) -> Result<WitnessDb, ClientError> {
+        <Self as WitnessInput>::witness_db(self)
+    }
+}
+
+impl WitnessInput for ClientExecutorRangeInput {
+    #[inline(always)]
+    fn state(&self) -> &EthereumState {
+        &self.parent_state
+    }
+
+    #[inline(always)]
+    fn state_anchor(&self) -> B256 {
+        self.parent_header().state_root
+    }
+
+    #[inline(always)]
+    fn state_requests(&self) -> impl Iterator<Item = (&Address, &Vec<U256>)> {
+        self.state_requests.iter()
+    }
+
+    #[inline(always)]
+    fn bytecodes(&self) -> impl Iterator<Item = &Bytecode> {
+        self.bytecodes.iter()
+    }
+
+    #[inline(always)]
+    fn headers(&self) -> impl Iterator<Item = &Header> {
+        self.blocks.iter().rev().map(|block| &block.header).chain(self.ancestor_headers.iter())
+    }
+}
+
+/// The result of executing a [`ClientExecutorRangeInput`].
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ClientExecutorRangeOutput {
+    /// The hash of the block preceding the range.
+    pub parent_hash: B256,
+    /// The hash of the first block in the range.
+    pub start_block_hash: B256,
+    /// The hash of the last block in the range.
+    pub end_block_hash: B256,
+}
+
+/// The version of the [`BlockPublicValues`] layout. Bumped whenever fields are added, removed or
+/// reordered.
+pub const PUBLIC_VALUES_VERSION: u32 = 1;
+
+sol! {
+    /// The public values committed by the client programs, ABI-encoded so that they can be decoded
+    /// by on-chain verifiers.
+    #[derive(Debug, PartialEq, Eq)]
+    struct BlockPublicValues {
+        /// The version of the layout, see [`PUBLIC_VALUES_VERSION`].
+        uint32 version;
+        /// The chain ID of the executed block.
+        uint64 chain_id;
+        /// The number of the executed block.
+        uint64 block_number;
+        /// The hash of the executed block.
+        bytes32 block_hash;
+        /// The hash of the parent block.
+        bytes32 parent_hash;
+        /// The state root after executing the block.
+        bytes32 state_root;
+        /// The receipts root of the executed block.
+        bytes32 receipts_root;
+        /// A commitment to the chain spec used to execute the block.
+        bytes32 chain_spec_hash;
+        /// The identity of the client program which executed the block, see
+        /// [`program_id`](crate::program_id).
+        bytes32 program_id;
+    }
+}
+
+impl BlockPublicValues {
+    /// Creates the public values for a derived block header executed by the given program under
+    /// the given chain spec.
+    pub fn new(
+        header: &Header,
+        block_hash: B256,
+        chain_spec: &ChainSpecConfig,
+        program_id: B256,
+    ) -> Self {
+        Self {
+            version: PUBLIC_VALUES_VERSION,
+            chain_id: chain_spec.chain_id,
+            block_number: header.number,
+            block_hash,
+            parent_hash: header.parent_hash,
+            state_root: header.state_root,
+            receipts_root: header.receipts_root,
+            chain_spec_hash: chain_spec.hash(),
+            program_id,
+        }
+    }
+}
+
 /// A trait for constructing [`WitnessDb`].
 pub trait WitnessInput {
     /// Gets a reference to the state from which account info and storage slots are loaded.
@@ -98,11 +285,15 @@ pub trait WitnessInput {
     /// implementing this trait causes a zkVM run to cost over 5M cycles more. To avoid this, define
     /// a method inside the type that calls this trait method instead.
     #[inline(always)]
-    fn witness_db(&self) -> Result<WitnessDb> {
+    fn witness_db(&self) -> Result<WitnessDb, ClientError> {
         let state = self.state();
 
-        if self.state_anchor() != state.state_root() {
-            eyre::bail!("parent state root mismatch");
+        let state_root = state.state_root();
+        if self.state_anchor() != state_root {
+            return Err(ClientError::ParentStateRootMismatch {
+                expected: self.state_anchor(),
+                actual: state_root,
+            });
         }
 
         let bytecodes_by_hash =
@@ -126,7 +317,7 @@ pub trait WitnessInput {
                         code: Some(
                             (*bytecodes_by_hash
                                 .get(&account_in_trie.code_hash)
-                                .ok_or_else(|| eyre::eyre!("missing bytecode"))?)
+                                .ok_or(ClientError::MissingBytecode(account_in_trie.code_hash))?)
                             // Cloning here is fine as `Bytes` is cheap to clone.
                             .to_owned(),
                         ),
@@ -141,7 +332,7 @@ pub trait WitnessInput {
                 let storage_trie = state
                     .storage_tries
                     .get(hashed_address)
-                    .ok_or_else(|| eyre::eyre!("parent state does not contain storage trie"))?;
+                    .ok_or(ClientError::MissingStorageTrie(address))?;
 
                 for &slot in slots {
                     let slot_value = storage_trie
@@ -158,16 +349,16 @@ pub trait WitnessInput {
         let mut block_hashes: HashMap<u64, B256> = HashMap::new();
         for (child_header, parent_header) in self.headers().tuple_windows() {
             if parent_header.number != child_header.number - 1 {
-                eyre::bail!("non-consecutive blocks");
+                return Err(ClientError::InvalidAncestorHeaders("non-consecutive blocks"));
             }
 
             if parent_header.hash_slow() != child_header.parent_hash {
-                eyre::bail!("parent hash mismatch");
+                return Err(ClientError::InvalidAncestorHeaders("parent hash mismatch"));
             }
 
             block_hashes.insert(parent_header.number, child_header.parent_hash);
         }
 
-        Ok(WitnessDb { accounts, storage, block_hashes })
+        Ok(WitnessDb::new(accounts, storage, block_hashes))
     }
 }
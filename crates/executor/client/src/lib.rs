@@ -1,3 +1,5 @@
+/// Client execution errors.
+pub mod error;
 /// Client program input data types.
 pub mod io;
 #[macro_use]
@@ -5,11 +7,16 @@ mod utils;
 
 pub mod custom;
 
-use std::{borrow::BorrowMut, fmt::Display};
+pub mod overrides;
+
+use std::{borrow::BorrowMut, fmt::Display, sync::Arc};
 
 use custom::CustomEvmConfig;
-use eyre::eyre;
-use io::ClientExecutorInput;
+use error::ClientError;
+use io::{
+    BlockPublicValues, ClientExecutorInput, ClientExecutorRangeInput, ClientExecutorRangeOutput,
+    ProfilingOptions,
+};
 use reth_chainspec::ChainSpec;
 use reth_errors::ProviderError;
 use reth_ethereum_consensus::validate_block_post_execution as validate_block_post_execution_ethereum;
@@ -18,9 +25,17 @@ use reth_evm_ethereum::execute::EthExecutorProvider;
 use reth_evm_optimism::OpExecutorProvider;
 use reth_execution_types::ExecutionOutcome;
 use reth_optimism_consensus::validate_block_post_execution as validate_block_post_execution_optimism;
-use reth_primitives::{proofs, Block, BlockWithSenders, Bloom, Header, Receipt, Receipts, Request};
-use revm::{db::CacheDB, Database};
-use revm_primitives::{address, U256};
+use reth_primitives::{
+    proofs, Block, BlockWithSenders, Bloom, Bytes, Header, Receipt, Receipts, Request, Signature,
+};
+use revm::{
+    db::{AccountState, BundleState, CacheDB, DbAccount},
+    precompile::PrecompileWithAddress,
+    Database,
+};
+use revm_primitives::{keccak256, Address, B256, U256};
+use rsp_mpt::EthereumState;
+use rsp_primitives::chain_spec::ChainSpecConfig;
 
 /// Chain ID for Ethereum Mainnet.
 pub const CHAIN_ID_ETH_MAINNET: u64 = 0x1;
@@ -31,6 +46,18 @@ pub const CHAIN_ID_OP_MAINNET: u64 = 0xa;
 /// Chain ID for Linea Mainnet.
 pub const CHAIN_ID_LINEA_MAINNET: u64 = 0xe708;
 
+/// Chain ID for Sepolia.
+pub const CHAIN_ID_SEPOLIA: u64 = 0xaa36a7;
+
+/// Chain ID for Holesky.
+pub const CHAIN_ID_HOLESKY: u64 = 0x4268;
+
+/// Chain ID for Base Mainnet.
+pub const CHAIN_ID_BASE_MAINNET: u64 = 0x2105;
+
+/// Chain ID for OP Sepolia.
+pub const CHAIN_ID_OP_SEPOLIA: u64 = 0xaa37dc;
+
 /// An executor that executes a block inside a zkVM.
 #[derive(Debug, Clone, Default)]
 pub struct ClientExecutor;
@@ -38,13 +65,35 @@ pub struct ClientExecutor;
 /// Trait for representing different execution/validation rules of different chain variants. This
 /// allows for dead code elimination to minimize the ELF size for each variant.
 pub trait Variant {
-    fn spec() -> ChainSpec;
+    /// The name of the execution rules of the variant, part of the identity of the client program
+    /// committed in the public values.
+    const NAME: &'static str;
 
     fn execute<DB>(
+        chain_spec: &ChainSpec,
+        executor_block_input: &BlockWithSenders,
+        executor_difficulty: U256,
+        cache_db: DB,
+    ) -> Result<BlockExecutionOutput<Receipt>, ClientError>
+    where
+        DB: Database<Error: Into<ProviderError> + Display>,
+    {
+        Self::execute_with_options(
+            chain_spec,
+            executor_block_input,
+            executor_difficulty,
+            cache_db,
+            ProfilingOptions::default(),
+        )
+    }
+
+    fn execute_with_options<DB>(
+        chain_spec: &ChainSpec,
         executor_block_input: &BlockWithSenders,
         executor_difficulty: U256,
         cache_db: DB,
-    ) -> eyre::Result<BlockExecutionOutput<Receipt>>
+        options: ProfilingOptions,
+    ) -> Result<BlockExecutionOutput<Receipt>, ClientError>
     where
         DB: Database<Error: Into<ProviderError> + Display>;
 
@@ -53,13 +102,31 @@ pub trait Variant {
         chain_spec: &ChainSpec,
         receipts: &[Receipt],
         requests: &[Request],
-    ) -> eyre::Result<()>;
+    ) -> Result<(), ClientError>;
+
+    fn pre_process_block(block: &Block) -> Result<Block, ClientError> {
+        Ok(block.clone())
+    }
 
-    fn pre_process_block(block: &Block) -> Block {
-        block.clone()
+    /// The precompiles replacing the reference implementations of revm at the same addresses,
+    /// e.g. implementations accelerated in the zkVM. As the host executes blocks with the same
+    /// variant, every block is checked against the state root of the chain with the overrides.
+    ///
+    /// The host also checks the overrides active for every block against the reference
+    /// implementations with [overrides::check_active_precompile_overrides] before executing it.
+    /// See [overrides::check_precompile_override] to test an override against them.
+    fn precompile_overrides() -> Vec<PrecompileWithAddress> {
+        Vec::new()
     }
 }
 
+/// Computes the identity of the client program executing blocks under the rules of the given
+/// variant: the keccak256 hash of the name and version of this crate and the name of the variant,
+/// separated by slashes.
+pub fn program_id<V: Variant>() -> B256 {
+    keccak256(format!("{}/{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"), V::NAME))
+}
+
 /// Implementation for Ethereum-specific execution/validation logic.
 #[derive(Debug)]
 pub struct EthereumVariant;
@@ -75,27 +142,106 @@ pub struct LineaVariant;
 /// EVM chain variants that implement different execution/validation rules.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ChainVariant {
-    /// Ethereum networks.
+    /// Ethereum Mainnet.
     Ethereum,
-    /// OP stack networks.
+    /// OP Mainnet.
     Optimism,
-    /// Linea networks.
+    /// Linea Mainnet.
     Linea,
+    /// Sepolia.
+    Sepolia,
+    /// Holesky.
+    Holesky,
+    /// Base Mainnet.
+    Base,
+    /// OP Sepolia.
+    OpSepolia,
 }
 
 impl ChainVariant {
+    /// Returns the variant for the given chain ID, if known.
+    pub fn from_chain_id(chain_id: u64) -> Option<Self> {
+        match chain_id {
+            CHAIN_ID_ETH_MAINNET => Some(ChainVariant::Ethereum),
+            CHAIN_ID_OP_MAINNET => Some(ChainVariant::Optimism),
+            CHAIN_ID_LINEA_MAINNET => Some(ChainVariant::Linea),
+            CHAIN_ID_SEPOLIA => Some(ChainVariant::Sepolia),
+            CHAIN_ID_HOLESKY => Some(ChainVariant::Holesky),
+            CHAIN_ID_BASE_MAINNET => Some(ChainVariant::Base),
+            CHAIN_ID_OP_SEPOLIA => Some(ChainVariant::OpSepolia),
+            _ => None,
+        }
+    }
+
     /// Returns the chain ID for the given variant.
     pub fn chain_id(&self) -> u64 {
         match self {
             ChainVariant::Ethereum => CHAIN_ID_ETH_MAINNET,
             ChainVariant::Optimism => CHAIN_ID_OP_MAINNET,
             ChainVariant::Linea => CHAIN_ID_LINEA_MAINNET,
+            ChainVariant::Sepolia => CHAIN_ID_SEPOLIA,
+            ChainVariant::Holesky => CHAIN_ID_HOLESKY,
+            ChainVariant::Base => CHAIN_ID_BASE_MAINNET,
+            ChainVariant::OpSepolia => CHAIN_ID_OP_SEPOLIA,
+        }
+    }
+
+    /// Returns the built-in chain spec for the given variant.
+    pub fn spec(&self) -> ChainSpecConfig {
+        match self {
+            ChainVariant::Ethereum => ChainSpecConfig::mainnet(),
+            ChainVariant::Optimism => ChainSpecConfig::op_mainnet(),
+            ChainVariant::Linea => ChainSpecConfig::linea_mainnet(),
+            ChainVariant::Sepolia => ChainSpecConfig::sepolia(),
+            ChainVariant::Holesky => ChainSpecConfig::holesky(),
+            ChainVariant::Base => ChainSpecConfig::base_mainnet(),
+            ChainVariant::OpSepolia => ChainSpecConfig::op_sepolia(),
+        }
+    }
+
+    /// Returns the identity of the client program executing blocks of the given variant, as
+    /// committed in the public values.
+    pub fn program_id(&self) -> B256 {
+        match self {
+            ChainVariant::Ethereum | ChainVariant::Sepolia | ChainVariant::Holesky => {
+                program_id::<EthereumVariant>()
+            }
+            ChainVariant::Optimism | ChainVariant::Base | ChainVariant::OpSepolia => {
+                program_id::<OptimismVariant>()
+            }
+            ChainVariant::Linea => program_id::<LineaVariant>(),
         }
     }
+
+    /// Returns whether the variant is an OP stack network.
+    pub fn is_optimism(&self) -> bool {
+        matches!(self, ChainVariant::Optimism | ChainVariant::Base | ChainVariant::OpSepolia)
+    }
 }
 
 impl ClientExecutor {
-    pub fn execute<V>(&self, mut input: ClientExecutorInput) -> eyre::Result<Header>
+    /// Executes the block in the input and returns the derived header along with the public
+    /// values to be committed by the client program.
+    pub fn execute<V>(
+        &self,
+        input: ClientExecutorInput,
+    ) -> Result<(Header, BlockPublicValues), ClientError>
+    where
+        V: Variant,
+    {
+        let (header, public_values, _) =
+            self.execute_with_options::<V>(input, ProfilingOptions::default())?;
+        Ok((header, public_values))
+    }
+
+    /// Executes the block in the input with the given profiling options, and returns the derived
+    /// header and public values along with the outcome of the execution, e.g. to inspect the
+    /// receipts.
+    pub fn execute_with_options<V>(
+        &self,
+        mut input: ClientExecutorInput,
+        options: ProfilingOptions,
+    ) -> Result<(Header, BlockPublicValues, ExecutionOutcome), ClientError>
     where
         V: Variant,
     {
@@ -103,25 +249,121 @@ impl ClientExecutor {
         let witness_db = input.witness_db()?;
         let cache_db = CacheDB::new(&witness_db);
 
+        // Setup the chain spec.
+        let spec = ChainSpec::try_from(&input.chain_spec)?;
+        input
+            .chain_spec
+            .check_supported(input.current_block.number, input.current_block.timestamp)
+            .map_err(ClientError::UnsupportedBlock)?;
+
+        // Execute the block and verify the resulting state root.
+        let parent_hash = input.parent_header().hash_slow();
+        let (header, executor_outcome) = self
+            .execute_block::<V, _>(
+                &spec,
+                &input.current_block,
+                parent_hash,
+                &mut input.parent_state,
+                cache_db,
+                options,
+            )
+            .map_err(|err| err.with_witness_error(&witness_db))?;
+
+        // Derive the public values.
+        let public_values = profile!("derive public values", {
+            BlockPublicValues::new(
+                &header,
+                header.hash_slow(),
+                &input.chain_spec,
+                program_id::<V>(),
+            )
+        });
+
+        Ok((header, public_values, executor_outcome))
+    }
+
+    /// Executes a range of consecutive blocks against a single witness, applying the state changes
+    /// of each block before executing the next one.
+    pub fn execute_range<V>(
+        &self,
+        mut input: ClientExecutorRangeInput,
+    ) -> Result<ClientExecutorRangeOutput, ClientError>
+    where
+        V: Variant,
+    {
+        if input.blocks.is_empty() {
+            return Err(ClientError::EmptyBlockRange);
+        }
+
+        // Initialize the witnessed database with verified storage proofs. The cache is shared
+        // across blocks so that later blocks observe the changes made by earlier ones.
+        let witness_db = input.witness_db()?;
+        let mut cache_db = CacheDB::new(&witness_db);
+
+        // Setup the chain spec.
+        let spec = ChainSpec::try_from(&input.chain_spec)?;
+
+        let parent_hash = input.parent_header().hash_slow();
+        let mut block_hash = parent_hash;
+        let mut start_block_hash = None;
+        for block in input.blocks.iter() {
+            input
+                .chain_spec
+                .check_supported(block.number, block.timestamp)
+                .map_err(ClientError::UnsupportedBlock)?;
+            let (header, executor_outcome) = self
+                .execute_block::<V, _>(
+                    &spec,
+                    block,
+                    block_hash,
+                    &mut input.parent_state,
+                    &mut cache_db,
+                    ProfilingOptions::default(),
+                )
+                .map_err(|err| err.with_witness_error(&witness_db))?;
+            commit_bundle_state(&mut cache_db, executor_outcome.state());
+
+            block_hash = header.hash_slow();
+            start_block_hash.get_or_insert(block_hash);
+        }
+
+        Ok(ClientExecutorRangeOutput {
+            parent_hash,
+            start_block_hash: start_block_hash.unwrap_or(block_hash),
+            end_block_hash: block_hash,
+        })
+    }
+
+    /// Executes a single block on top of `state`, verifies the resulting state root and derives
+    /// the block header.
+    fn execute_block<V, DB>(
+        &self,
+        spec: &ChainSpec,
+        block: &Block,
+        parent_hash: B256,
+        state: &mut EthereumState,
+        db: DB,
+        options: ProfilingOptions,
+    ) -> Result<(Header, ExecutionOutcome), ClientError>
+    where
+        V: Variant,
+        DB: Database<Error: Into<ProviderError> + Display>,
+    {
         // Execute the block.
-        let spec = V::spec();
+        let executor_block_input = profile!("pre-process block", { V::pre_process_block(block) })?;
         let executor_block_input = profile!("recover senders", {
-            input
-                .current_block
-                .clone()
-                .with_recovered_senders()
-                .ok_or(eyre!("failed to recover senders"))
+            executor_block_input.with_recovered_senders().ok_or(ClientError::SenderRecovery)
         })?;
-        let executor_difficulty = input.current_block.header.difficulty;
+        let executor_difficulty = block.header.difficulty;
         let executor_output = profile!("execute", {
-            V::execute(&executor_block_input, executor_difficulty, cache_db)
+            V::execute_with_options(spec, &executor_block_input, executor_difficulty, db, options)
         })?;
 
         // Validate the block post execution.
         profile!("validate block post-execution", {
             V::validate_block_post_execution(
                 &executor_block_input,
-                &spec,
+                spec,
                 &executor_output.receipts,
                 &executor_output.requests,
             )
@@ -139,58 +381,137 @@ impl ClientExecutor {
         let executor_outcome = ExecutionOutcome::new(
             executor_output.state,
             Receipts::from(executor_output.receipts),
-            input.current_block.header.number,
+            block.header.number,
             vec![executor_output.requests.into()],
         );
 
         // Verify the state root.
         let state_root = profile!("compute state root", {
-            input.parent_state.update(&executor_outcome.hash_state_slow());
-            input.parent_state.state_root()
-        });
+            state.update(&executor_outcome.hash_state_slow())?;
+            Ok::<_, ClientError>(state.state_root())
+        })?;
 
-        if state_root != input.current_block.state_root {
-            eyre::bail!("mismatched state root");
+        if state_root != block.state_root {
+            return Err(ClientError::StateRootMismatch {
+                expected: block.state_root,
+                actual: state_root,
+            });
         }
 
         // Derive the block header.
         //
         // Note: the receipts root and gas used are verified by `validate_block_post_execution`.
-        let mut header = input.current_block.header.clone();
-        header.parent_hash = input.parent_header().hash_slow();
-        header.ommers_hash = proofs::calculate_ommers_root(&input.current_block.ommers);
-        header.state_root = input.current_block.state_root;
-        header.transactions_root = proofs::calculate_transaction_root(&input.current_block.body);
-        header.receipts_root = input.current_block.header.receipts_root;
-        header.withdrawals_root = input
-            .current_block
+        let mut header = block.header.clone();
+        header.parent_hash = parent_hash;
+        header.ommers_hash = proofs::calculate_ommers_root(&block.ommers);
+        header.state_root = block.state_root;
+        header.transactions_root = proofs::calculate_transaction_root(&block.body);
+        header.receipts_root = block.header.receipts_root;
+        header.withdrawals_root = block
             .withdrawals
             .clone()
             .map(|w| proofs::calculate_withdrawals_root(w.into_inner().as_slice()));
         header.logs_bloom = logs_bloom;
         header.requests_root =
-            input.current_block.requests.as_ref().map(|r| proofs::calculate_requests_root(&r.0));
+            block.requests.as_ref().map(|r| proofs::calculate_requests_root(&r.0));
 
-        Ok(header)
+        Ok((header, executor_outcome))
     }
 }
 
-impl Variant for EthereumVariant {
-    fn spec() -> ChainSpec {
-        rsp_primitives::chain_spec::mainnet()
+/// Recovers the signer of a clique block from the seal at the end of its header extra data.
+///
+/// The extra data is laid out as follows:
+/// - vanity: 32 bytes
+/// - signers (checkpoint blocks only): 20 bytes each
+/// - seal: 65 bytes
+///
+/// The seal is a secp256k1 signature over the hash of the header with the seal stripped from the
+/// extra data.
+pub fn recover_clique_signer(header: &Header) -> Result<Address, ClientError> {
+    const EXTRA_VANITY: usize = 32;
+    const EXTRA_SEAL: usize = 65;
+
+    if header.extra_data.len() < EXTRA_VANITY + EXTRA_SEAL {
+        return Err(ClientError::InvalidCliqueSeal("extra data too short"));
     }
+    let (unsealed, seal) = header.extra_data.split_at(header.extra_data.len() - EXTRA_SEAL);
+
+    let mut unsealed_header = header.clone();
+    unsealed_header.extra_data = Bytes::copy_from_slice(unsealed);
+    let seal_hash = unsealed_header.hash_slow();
+
+    let odd_y_parity = match seal[64] {
+        0 => false,
+        1 => true,
+        _ => return Err(ClientError::InvalidCliqueSeal("invalid recovery id")),
+    };
+    let signature = Signature {
+        r: U256::from_be_slice(&seal[..32]),
+        s: U256::from_be_slice(&seal[32..64]),
+        odd_y_parity,
+    };
+
+    signature
+        .recover_signer_unchecked(seal_hash)
+        .ok_or(ClientError::InvalidCliqueSeal("failed to recover signer"))
+}
 
-    fn execute<DB>(
+/// Commits the post-execution state of a block into a [CacheDB], so that blocks executed
+/// afterwards against the same database observe the changes.
+pub fn commit_bundle_state<ExtDB>(cache_db: &mut CacheDB<ExtDB>, bundle: &BundleState) {
+    for (code_hash, code) in bundle.contracts.iter() {
+        cache_db.contracts.entry(*code_hash).or_insert_with(|| code.clone());
+    }
+
+    for (address, account) in bundle.state() {
+        let db_account = cache_db.accounts.entry(*address).or_default();
+        match &account.info {
+            Some(info) => {
+                if account.was_destroyed() {
+                    db_account.storage.clear();
+                    db_account.account_state = AccountState::StorageCleared;
+                } else if db_account.account_state != AccountState::StorageCleared {
+                    db_account.account_state = AccountState::Touched;
+                }
+
+                // Bundle accounts may not carry the code, in which case the one already loaded is
+                // kept as long as the code hash did not change.
+                let mut info = info.clone();
+                if info.code.is_none() && db_account.info.code_hash == info.code_hash {
+                    info.code = db_account.info.code.take();
+                }
+                db_account.info = info;
+
+                for (slot, value) in account.storage.iter() {
+                    db_account.storage.insert(*slot, value.present_value);
+                }
+            }
+            None => {
+                *db_account = DbAccount::new_not_existing();
+            }
+        }
+    }
+}
+
+impl Variant for EthereumVariant {
+    const NAME: &'static str = "ethereum";
+
+    fn execute_with_options<DB>(
+        chain_spec: &ChainSpec,
         executor_block_input: &BlockWithSenders,
         executor_difficulty: U256,
         cache_db: DB,
-    ) -> eyre::Result<BlockExecutionOutput<Receipt>>
+        options: ProfilingOptions,
+    ) -> Result<BlockExecutionOutput<Receipt>, ClientError>
     where
         DB: Database<Error: Into<ProviderError> + Display>,
     {
         Ok(EthExecutorProvider::new(
-            Self::spec().into(),
-            CustomEvmConfig::from_variant(ChainVariant::Ethereum),
+            Arc::new(chain_spec.clone()),
+            CustomEvmConfig::from_variant(ChainVariant::Ethereum)
+                .with_precompile_overrides(Self::precompile_overrides())
+                .with_profiling(options, executor_block_input),
         )
         .executor(cache_db)
         .execute((executor_block_input, executor_difficulty).into())?)
@@ -201,27 +522,29 @@ impl Variant for EthereumVariant {
         chain_spec: &ChainSpec,
         receipts: &[Receipt],
         requests: &[Request],
-    ) -> eyre::Result<()> {
+    ) -> Result<(), ClientError> {
         Ok(validate_block_post_execution_ethereum(block, chain_spec, receipts, requests)?)
     }
 }
 
 impl Variant for OptimismVariant {
-    fn spec() -> ChainSpec {
-        rsp_primitives::chain_spec::op_mainnet()
-    }
+    const NAME: &'static str = "optimism";
 
-    fn execute<DB>(
+    fn execute_with_options<DB>(
+        chain_spec: &ChainSpec,
         executor_block_input: &BlockWithSenders,
         executor_difficulty: U256,
         cache_db: DB,
-    ) -> eyre::Result<BlockExecutionOutput<Receipt>>
+        options: ProfilingOptions,
+    ) -> Result<BlockExecutionOutput<Receipt>, ClientError>
     where
         DB: Database<Error: Into<ProviderError> + Display>,
     {
         Ok(OpExecutorProvider::new(
-            Self::spec().into(),
-            CustomEvmConfig::from_variant(ChainVariant::Optimism),
+            Arc::new(chain_spec.clone()),
+            CustomEvmConfig::from_variant(ChainVariant::Optimism)
+                .with_precompile_overrides(Self::precompile_overrides())
+                .with_profiling(options, executor_block_input),
         )
         .executor(cache_db)
         .execute((executor_block_input, executor_difficulty).into())?)
@@ -232,27 +555,29 @@ impl Variant for OptimismVariant {
         chain_spec: &ChainSpec,
         receipts: &[Receipt],
         _requests: &[Request],
-    ) -> eyre::Result<()> {
+    ) -> Result<(), ClientError> {
         Ok(validate_block_post_execution_optimism(block, chain_spec, receipts)?)
     }
 }
 
 impl Variant for LineaVariant {
-    fn spec() -> ChainSpec {
-        rsp_primitives::chain_spec::linea_mainnet()
-    }
+    const NAME: &'static str = "linea";
 
-    fn execute<DB>(
+    fn execute_with_options<DB>(
+        chain_spec: &ChainSpec,
         executor_block_input: &BlockWithSenders,
         executor_difficulty: U256,
         cache_db: DB,
-    ) -> eyre::Result<BlockExecutionOutput<Receipt>>
+        options: ProfilingOptions,
+    ) -> Result<BlockExecutionOutput<Receipt>, ClientError>
     where
         DB: Database<Error: Into<ProviderError> + Display>,
     {
         Ok(EthExecutorProvider::new(
-            Self::spec().into(),
-            CustomEvmConfig::from_variant(ChainVariant::Linea),
+            Arc::new(chain_spec.clone()),
+            CustomEvmConfig::from_variant(ChainVariant::Linea)
+                .with_precompile_overrides(Self::precompile_overrides())
+                .with_profiling(options, executor_block_input),
         )
         .executor(cache_db)
         .execute((executor_block_input, executor_difficulty).into())?)
@@ -263,26 +588,98 @@ impl Variant for LineaVariant {
         chain_spec: &ChainSpec,
         receipts: &[Receipt],
         requests: &[Request],
-    ) -> eyre::Result<()> {
+    ) -> Result<(), ClientError> {
         Ok(validate_block_post_execution_ethereum(block, chain_spec, receipts, requests)?)
     }
 
-    fn pre_process_block(block: &Block) -> Block {
+    fn pre_process_block(block: &Block) -> Result<Block, ClientError> {
         // Linea network uses clique consensus, which is not implemented in reth.
         // The main difference for the execution part is the block beneficiary:
         // reth will credit the block reward to the beneficiary address (coinbase)
         // whereas in clique, the block reward is credited to the signer.
 
-        // We extract the clique beneficiary address from the genesis extra data.
-        // - vanity: 32 bytes
-        // - address: 20 bytes
-        // - seal: 65 bytes
-        // we extract the address from the 32nd to 52nd byte.
-        let addr = address!("8f81e2e3f8b46467523463835f965ffe476e1c9e");
+        // We recover the signer from the seal in the header extra data, so that the execution is
+        // bound to the actual signer of the block.
+        let signer = recover_clique_signer(&block.header)?;
 
         // We hijack the beneficiary address here to match the clique consensus.
         let mut block = block.clone();
-        block.header.borrow_mut().beneficiary = addr;
-        block
+        block.header.borrow_mut().beneficiary = signer;
+        Ok(block)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use revm_primitives::{address, b256, bytes};
+
+    /// The header of Goerli block 4409378, sealed by one of the Goerli clique signers.
+    fn goerli_header() -> Header {
+        Header {
+            parent_hash: b256!("1a9bdc31fc785f8a95efeeb7ae58f40f6366b8e805f47447a52335c95f4ceb49"),
+            ommers_hash: b256!("1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347"),
+            state_root: b256!("f38c4bf2958e541ec6df148e54ce073dc6b610f8613147ede568cb7b5c2d81ee"),
+            transactions_root: b256!(
+                "56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421"
+            ),
+            receipts_root: b256!("56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421"),
+            difficulty: U256::from(1),
+            number: 4409378,
+            gas_limit: 8000000,
+            timestamp: 1615275696,
+            extra_data: bytes!("4e65746865726d696e6420312e392e32322d302d6463373666616366612d32308639ad8ff3d850a261f3b26bc2a55e0f3a718de0dd040a19a4ce37e7b473f2d7481448a1e1fd8fb69260825377c0478393e6055f471a5cf839467ce919a6ad2700"),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_recover_clique_signer() {
+        let header = goerli_header();
+        assert_eq!(
+            header.hash_slow(),
+            b256!("a4856602944fdfd18c528ef93cc52a681b38d766a7e39c27a47488c8461adcb0")
+        );
+        assert_eq!(
+            recover_clique_signer(&header).unwrap(),
+            address!("000000568b9b5a365eaa767d42e74ed88915c204")
+        );
+    }
+
+    #[test]
+    fn test_recover_clique_signer_truncated_seal() {
+        let mut header = goerli_header();
+        header.extra_data = header.extra_data.slice(..96);
+        assert!(matches!(
+            recover_clique_signer(&header),
+            Err(ClientError::InvalidCliqueSeal("extra data too short"))
+        ));
+    }
+
+    #[test]
+    fn test_recover_clique_signer_corrupted_seal() {
+        let signer = recover_clique_signer(&goerli_header()).unwrap();
+
+        // A corrupted signature recovers another signer, or none at all.
+        let mut header = goerli_header();
+        let mut extra_data = header.extra_data.to_vec();
+        extra_data[40] ^= 0xff;
+        header.extra_data = extra_data.into();
+        assert!(recover_clique_signer(&header).map_or(true, |recovered| recovered != signer));
+
+        // Clique seals use the raw recovery ID, so a legacy `v` of 27 is invalid.
+        let mut header = goerli_header();
+        let mut extra_data = header.extra_data.to_vec();
+        *extra_data.last_mut().unwrap() = 27;
+        header.extra_data = extra_data.into();
+        assert!(matches!(
+            recover_clique_signer(&header),
+            Err(ClientError::InvalidCliqueSeal("invalid recovery id"))
+        ));
+
+        // So does a header modified after sealing.
+        let mut header = goerli_header();
+        header.timestamp += 1;
+        assert!(recover_clique_signer(&header).map_or(true, |recovered| recovered != signer));
     }
 }
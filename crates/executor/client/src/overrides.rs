@@ -0,0 +1,212 @@
+//! Differential testing of precompile overrides against the reference implementations of revm.
+//!
+//! Overrides are registered by a [Variant](crate::Variant) through
+//! [Variant::precompile_overrides](crate::Variant::precompile_overrides), and replace the
+//! reference implementations for both host and client execution. An override must return exactly
+//! the same results as the implementation it replaces, including the gas used and the errors, or
+//! blocks calling it will not match the state root of the chain.
+//!
+//! The host checks the overrides active for every block it executes with
+//! [check_active_precompile_overrides], and fails on any divergence.
+
+use reth_chainspec::ChainSpec;
+use reth_evm::ConfigureEvmEnv;
+use reth_primitives::{
+    revm_primitives::{CfgEnv, CfgEnvWithHandlerCfg, Env, SpecId},
+    Header,
+};
+use revm::precompile::{PrecompileResult, PrecompileWithAddress};
+use revm_primitives::{Address, Bytes};
+
+use crate::{
+    custom::{call_precompile, variant_precompiles, CustomEvmConfig},
+    ChainVariant,
+};
+
+/// The number of pseudo-random inputs each active override is checked with by
+/// [check_active_precompile_overrides].
+const ACTIVE_OVERRIDE_INPUTS: usize = 256;
+
+/// The maximum length of the pseudo-random inputs used by [check_active_precompile_overrides].
+const ACTIVE_OVERRIDE_MAX_INPUT_LEN: usize = 512;
+
+/// The maximum gas limit of the pseudo-random inputs used by [check_active_precompile_overrides].
+const ACTIVE_OVERRIDE_MAX_GAS: u64 = 1_000_000;
+
+/// Errors returned when checking a precompile override against its reference implementation.
+#[derive(Debug, thiserror::Error)]
+pub enum PrecompileOverrideError {
+    #[error("no precompile to override: address={address}, spec_id={spec_id:?}")]
+    UnknownPrecompile { address: Address, spec_id: SpecId },
+    #[error(
+        "precompile override mismatch: address={address}, input={input}, gas_limit={gas_limit}, \
+         expected={expected:?}, actual={actual:?}"
+    )]
+    Mismatch {
+        address: Address,
+        input: Bytes,
+        gas_limit: u64,
+        expected: PrecompileResult,
+        actual: PrecompileResult,
+    },
+}
+
+/// Checks that a precompile override returns the same results as the reference implementation of
+/// the chain variant at the given spec, for every input and gas limit.
+///
+/// Fails with the first input for which the results differ.
+pub fn check_precompile_override(
+    variant: ChainVariant,
+    spec_id: SpecId,
+    precompile_override: &PrecompileWithAddress,
+    inputs: impl IntoIterator<Item = (Bytes, u64)>,
+) -> Result<(), PrecompileOverrideError> {
+    let PrecompileWithAddress(address, candidate) = precompile_override;
+    let precompiles = variant_precompiles(variant, spec_id);
+    let Some(reference) = precompiles.get(address) else {
+        return Err(PrecompileOverrideError::UnknownPrecompile { address: *address, spec_id });
+    };
+
+    let env = Env::default();
+    for (input, gas_limit) in inputs {
+        let expected = call_precompile(reference, &input, gas_limit, &env);
+        let actual = call_precompile(candidate, &input, gas_limit, &env);
+        if actual != expected {
+            return Err(PrecompileOverrideError::Mismatch {
+                address: *address,
+                input,
+                gas_limit,
+                expected,
+                actual,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks the precompile overrides which are active at the spec of a block against the reference
+/// implementations of the chain variant, with pseudo-random inputs seeded by the block number.
+///
+/// Overrides of precompiles which are not active at the spec of the block are skipped, as they
+/// are ignored during its execution.
+pub fn check_active_precompile_overrides(
+    variant: ChainVariant,
+    precompile_overrides: &[PrecompileWithAddress],
+    chain_spec: &ChainSpec,
+    header: &Header,
+) -> Result<(), PrecompileOverrideError> {
+    if precompile_overrides.is_empty() {
+        return Ok(());
+    }
+
+    let mut cfg_env = CfgEnvWithHandlerCfg::new_with_spec_id(CfgEnv::default(), SpecId::LATEST);
+    CustomEvmConfig::from_variant(variant).fill_cfg_env(
+        &mut cfg_env,
+        chain_spec,
+        header,
+        header.difficulty,
+    );
+    let spec_id = cfg_env.handler_cfg.spec_id;
+
+    let precompiles = variant_precompiles(variant, spec_id);
+    for precompile_override in precompile_overrides {
+        if !precompiles.contains(&precompile_override.0) {
+            continue;
+        }
+
+        check_precompile_override(
+            variant,
+            spec_id,
+            precompile_override,
+            random_inputs(header.number, ACTIVE_OVERRIDE_MAX_INPUT_LEN, ACTIVE_OVERRIDE_MAX_GAS)
+                .take(ACTIVE_OVERRIDE_INPUTS),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Returns an endless sequence of pseudo-random inputs of up to `max_len` bytes, along with gas
+/// limits of up to `max_gas`.
+///
+/// The sequence only depends on the seed, so that mismatches can be reproduced. Half of the inputs
+/// get the full `max_gas`, so that most calls don't run out of gas.
+pub fn random_inputs(
+    seed: u64,
+    max_len: usize,
+    max_gas: u64,
+) -> impl Iterator<Item = (Bytes, u64)> {
+    let mut rng = SplitMix64(seed);
+    std::iter::repeat_with(move || {
+        let len = (rng.next_u64() % (max_len as u64 + 1)) as usize;
+        let input = (0..len).map(|_| rng.next_u64() as u8).collect::<Bytes>();
+        let gas_limit =
+            if rng.next_u64() % 2 == 0 { max_gas } else { rng.next_u64() % (max_gas + 1) };
+        (input, gas_limit)
+    })
+}
+
+/// The SplitMix64 generator, which is good enough for test inputs and has no dependencies.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use revm::precompile::{u64_to_address, Precompile, PrecompileError, PrecompileOutput};
+
+    /// A reimplementation of the identity precompile.
+    fn identity(input: &Bytes, gas_limit: u64) -> PrecompileResult {
+        let gas_used = 15 + 3 * (input.len() as u64).div_ceil(32);
+        if gas_used > gas_limit {
+            return Err(PrecompileError::OutOfGas.into());
+        }
+        Ok(PrecompileOutput::new(gas_used, input.clone()))
+    }
+
+    /// A broken sha256 precompile, which returns its input.
+    fn sha256(input: &Bytes, _gas_limit: u64) -> PrecompileResult {
+        Ok(PrecompileOutput::new(60, input.clone()))
+    }
+
+    #[test]
+    fn test_check_precompile_override() {
+        let identity = PrecompileWithAddress(u64_to_address(4), Precompile::Standard(identity));
+        check_precompile_override(
+            ChainVariant::Ethereum,
+            SpecId::CANCUN,
+            &identity,
+            random_inputs(1, 256, 64).take(1000),
+        )
+        .unwrap();
+
+        let sha256 = PrecompileWithAddress(u64_to_address(2), Precompile::Standard(sha256));
+        let result = check_precompile_override(
+            ChainVariant::Ethereum,
+            SpecId::CANCUN,
+            &sha256,
+            random_inputs(1, 256, 1000).take(1000),
+        );
+        assert!(matches!(result, Err(PrecompileOverrideError::Mismatch { .. })));
+
+        // The P256VERIFY precompile is only active on OP-stack chains.
+        let p256_verify = PrecompileWithAddress(u64_to_address(0x100), identity.1);
+        let result = check_precompile_override(
+            ChainVariant::Ethereum,
+            SpecId::CANCUN,
+            &p256_verify,
+            random_inputs(1, 256, 64).take(1),
+        );
+        assert!(matches!(result, Err(PrecompileOverrideError::UnknownPrecompile { .. })));
+    }
+}
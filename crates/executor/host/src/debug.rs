@@ -0,0 +1,302 @@
+//! Debugging of blocks whose computed post-state doesn't match the state root of the chain.
+
+use std::{collections::BTreeMap, fmt};
+
+use alloy_provider::{network::AnyNetwork, Provider};
+use alloy_transport::Transport;
+use eyre::eyre;
+use futures::StreamExt;
+use reth_chainspec::ChainSpec;
+use reth_evm::{ConfigureEvm, ConfigureEvmEnv};
+use reth_execution_types::ExecutionOutcome;
+use reth_primitives::{
+    revm_primitives::{
+        AccountInfo, BlockEnv, CfgEnv, CfgEnvWithHandlerCfg, EnvWithHandlerCfg, ResultAndState,
+        SpecId, TxEnv,
+    },
+    Account, Address, BlockWithSenders, B256, U256,
+};
+use revm::{
+    db::{BundleState, CacheDB},
+    interpreter::{opcode, Interpreter},
+    Database, DatabaseCommit, EvmContext, Inspector,
+};
+use rsp_client_executor::{custom::CustomEvmConfig, io::ProfilingOptions, ChainVariant, Variant};
+use rsp_rpc_db::RpcDb;
+
+use crate::{HostExecutor, ProofRequest};
+
+/// A write to a storage slot by the `SSTORE` opcode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StorageWrite {
+    pub address: Address,
+    pub slot: U256,
+    pub value: U256,
+}
+
+/// An inspector recording every storage write of the transaction being executed, including the
+/// writes which are later reverted.
+#[derive(Debug, Default)]
+pub struct StorageWriteInspector {
+    pub writes: Vec<StorageWrite>,
+}
+
+impl<DB: Database> Inspector<DB> for StorageWriteInspector {
+    fn step(&mut self, interp: &mut Interpreter, _context: &mut EvmContext<DB>) {
+        if interp.current_opcode() != opcode::SSTORE {
+            return;
+        }
+
+        // Stack underflows are left to the interpreter to report.
+        if let (Ok(slot), Ok(value)) = (interp.stack.peek(0), interp.stack.peek(1)) {
+            self.writes.push(StorageWrite { address: interp.contract.target_address, slot, value });
+        }
+    }
+}
+
+/// The changes made by a transaction to an account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountDiff {
+    /// The account before the transaction, or `None` if it didn't exist.
+    pub pre: Option<Account>,
+    /// The account after the transaction, or `None` if it doesn't exist anymore.
+    pub post: Option<Account>,
+    /// The values of the changed storage slots before and after the transaction.
+    pub storage: BTreeMap<U256, (U256, U256)>,
+}
+
+/// The changes made by a transaction to the state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxStateDiff {
+    pub index: usize,
+    pub hash: B256,
+    pub accounts: BTreeMap<Address, AccountDiff>,
+    /// Every storage write of the transaction, in execution order.
+    pub storage_writes: Vec<StorageWrite>,
+}
+
+/// The first difference, in address order, between the computed post-state of a block and the
+/// state of the chain after the block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateMismatch {
+    Account { address: Address, expected: Option<Account>, actual: Option<Account> },
+    Slot { address: Address, slot: U256, expected: U256, actual: U256 },
+}
+
+impl StateMismatch {
+    fn address(&self) -> Address {
+        match self {
+            Self::Account { address, .. } | Self::Slot { address, .. } => *address,
+        }
+    }
+}
+
+impl fmt::Display for StateMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Account { address, expected, actual } => write!(
+                f,
+                "account mismatch: address={}, expected={:?}, actual={:?}",
+                address, expected, actual
+            ),
+            Self::Slot { address, slot, expected, actual } => write!(
+                f,
+                "storage slot mismatch: address={}, slot={}, expected={}, actual={}",
+                address, slot, expected, actual
+            ),
+        }
+    }
+}
+
+/// Executes the transactions of a block one by one with a [StorageWriteInspector] attached, and
+/// returns the changes each of them made to the state.
+///
+/// Only the transactions are executed: the system calls made before and after them, e.g. to the
+/// EIP-4788 beacon roots contract, as well as withdrawals and block rewards, are not part of the
+/// diffs.
+pub fn trace_state_diffs<DB>(
+    evm_config: &CustomEvmConfig,
+    spec: &ChainSpec,
+    block: &BlockWithSenders,
+    db: DB,
+) -> eyre::Result<Vec<TxStateDiff>>
+where
+    DB: Database<Error: fmt::Display> + DatabaseCommit,
+{
+    let mut cfg = CfgEnvWithHandlerCfg::new_with_spec_id(CfgEnv::default(), SpecId::LATEST);
+    let mut block_env = BlockEnv::default();
+    evm_config.fill_cfg_and_block_env(
+        &mut cfg,
+        &mut block_env,
+        spec,
+        &block.header,
+        block.header.difficulty,
+    );
+    let env = EnvWithHandlerCfg::new_with_cfg_env(cfg, block_env, TxEnv::default());
+    let mut evm = evm_config.evm_with_env_and_inspector(db, env, StorageWriteInspector::default());
+
+    let mut diffs = Vec::with_capacity(block.body.len());
+    for (index, (transaction, sender)) in block.body.iter().zip(&block.senders).enumerate() {
+        evm_config.fill_tx_env(evm.tx_mut(), transaction, *sender);
+        let ResultAndState { state, .. } = evm
+            .transact()
+            .map_err(|err| eyre!("failed to execute transaction {}: {}", index, err))?;
+
+        let mut accounts = BTreeMap::new();
+        for (address, account) in state.iter().filter(|(_, account)| account.is_touched()) {
+            let pre = evm
+                .db_mut()
+                .basic(*address)
+                .map_err(|err| eyre!("failed to load account {}: {}", address, err))?;
+            let diff = AccountDiff {
+                pre: pre.as_ref().and_then(to_account),
+                post: if account.is_selfdestructed() { None } else { to_account(&account.info) },
+                storage: account
+                    .storage
+                    .iter()
+                    .filter(|(_, slot)| slot.is_changed())
+                    .map(|(key, slot)| (*key, (slot.original_value, slot.present_value)))
+                    .collect(),
+            };
+            if diff.pre != diff.post || !diff.storage.is_empty() {
+                accounts.insert(*address, diff);
+            }
+        }
+        evm.db_mut().commit(state);
+
+        diffs.push(TxStateDiff {
+            index,
+            hash: transaction.hash(),
+            accounts,
+            storage_writes: std::mem::take(&mut evm.context.external.writes),
+        });
+    }
+
+    Ok(diffs)
+}
+
+/// Logs a state mismatch along with the changes made by every transaction to the mismatched
+/// account.
+pub fn log_state_mismatch(mismatch: &StateMismatch, diffs: &[TxStateDiff]) {
+    tracing::error!("{}", mismatch);
+
+    let address = mismatch.address();
+    for diff in diffs {
+        let Some(account) = diff.accounts.get(&address) else {
+            continue;
+        };
+
+        match mismatch {
+            StateMismatch::Account { .. } => tracing::error!(
+                "tx {} ({}) changed the account: pre={:?}, post={:?}",
+                diff.index,
+                diff.hash,
+                account.pre,
+                account.post
+            ),
+            StateMismatch::Slot { slot, .. } => {
+                if let Some((pre, post)) = account.storage.get(slot) {
+                    tracing::error!(
+                        "tx {} ({}) changed the slot: pre={}, post={}",
+                        diff.index,
+                        diff.hash,
+                        pre,
+                        post
+                    );
+                }
+                for write in diff.storage_writes.iter() {
+                    if write.address == address && write.slot == *slot {
+                        tracing::error!("tx {} ({}) wrote {}", diff.index, diff.hash, write.value);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<T: Transport + Clone, P: Provider<T, AnyNetwork> + Clone> HostExecutor<T, P> {
+    /// Traces the state changes of every transaction of a block whose state root doesn't match,
+    /// and logs the first account or storage slot which differs from the chain after the block.
+    pub(crate) async fn debug_state_root_mismatch<V: Variant>(
+        &self,
+        variant: ChainVariant,
+        spec: &ChainSpec,
+        block: &BlockWithSenders,
+        rpc_db: &RpcDb<T, P>,
+        executor_outcome: &ExecutionOutcome,
+    ) -> eyre::Result<()> {
+        tracing::info!("tracing the state changes of every transaction");
+        let evm_config = CustomEvmConfig::from_variant(variant)
+            .with_precompile_overrides(V::precompile_overrides())
+            .with_profiling(ProfilingOptions::none(), block);
+        let diffs = trace_state_diffs(&evm_config, spec, block, CacheDB::new(rpc_db))?;
+
+        tracing::info!("comparing the post-state against the proofs of the chain");
+        match self.find_state_mismatch(block.header.number, executor_outcome.state()).await? {
+            Some(mismatch) => log_state_mismatch(&mismatch, &diffs),
+            None => tracing::error!(
+                "no account or storage slot changed by the block differs from the chain"
+            ),
+        }
+
+        Ok(())
+    }
+
+    /// Compares the computed post-state of a block against the proofs of the chain after the
+    /// block, for every account and storage slot changed by the block, and returns the first
+    /// difference.
+    pub(crate) async fn find_state_mismatch(
+        &self,
+        block_number: u64,
+        bundle: &BundleState,
+    ) -> eyre::Result<Option<StateMismatch>> {
+        let accounts = bundle.state.iter().collect::<BTreeMap<_, _>>();
+        let mut proofs = futures::stream::iter(accounts.iter())
+            .map(|(address, account)| {
+                let keys = account.storage.keys().map(|slot| B256::from(*slot)).collect();
+                self.fetch_proof(ProofRequest::new(**address, keys, block_number))
+            })
+            .buffered(self.rpc_concurrency);
+
+        for (address, account) in accounts.iter() {
+            let proof = proofs.next().await.ok_or(eyre!("missing proof: {}", address))??;
+
+            let actual = account.info.as_ref().and_then(to_account);
+            if proof.info != actual {
+                return Ok(Some(StateMismatch::Account {
+                    address: **address,
+                    expected: proof.info,
+                    actual,
+                }));
+            }
+            if actual.is_none() {
+                continue;
+            }
+
+            for storage_proof in proof.storage_proofs.iter() {
+                let slot = U256::from_be_bytes(storage_proof.key.0);
+                let actual = account.storage.get(&slot).map(|slot| slot.present_value);
+                if actual != Some(storage_proof.value) {
+                    return Ok(Some(StateMismatch::Slot {
+                        address: **address,
+                        slot,
+                        expected: storage_proof.value,
+                        actual: actual.unwrap_or_default(),
+                    }));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Converts an account info to the account stored in the state trie, or `None` for empty
+/// accounts, which are not stored.
+fn to_account(info: &AccountInfo) -> Option<Account> {
+    (!info.is_empty()).then_some(Account {
+        nonce: info.nonce,
+        balance: info.balance,
+        bytecode_hash: Some(info.code_hash),
+    })
+}
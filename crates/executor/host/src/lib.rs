@@ -1,23 +1,75 @@
 use std::{collections::BTreeSet, marker::PhantomData};
 
 use alloy_provider::{network::AnyNetwork, Provider};
+use alloy_rpc_types::BlockNumberOrTag;
+use alloy_sol_types::SolValue;
 use alloy_transport::Transport;
 use eyre::{eyre, Ok};
+use futures::{StreamExt, TryStreamExt};
+use reth_chainspec::ChainSpec;
+use reth_evm::execute::BlockExecutionOutput;
 use reth_execution_types::ExecutionOutcome;
-use reth_primitives::{proofs, Block, Bloom, Receipts, B256};
+use reth_primitives::{
+    proofs, revm_primitives::Bytecode, Address, Block, BlockWithSenders, Bloom, Header, Receipt,
+    Receipts, B256, U256,
+};
+use reth_trie::{AccountProof, HashedPostState};
 use revm::db::CacheDB;
+use revm_primitives::HashMap;
 use rsp_client_executor::{
-    io::ClientExecutorInput, ChainVariant, EthereumVariant, LineaVariant, OptimismVariant, Variant,
+    commit_bundle_state,
+    io::{BlockPublicValues, ClientExecutorInput, ClientExecutorRangeInput, PUBLIC_VALUES_VERSION},
+    overrides::check_active_precompile_overrides,
+    ChainVariant, EthereumVariant, LineaVariant, OptimismVariant, Variant,
 };
 use rsp_mpt::EthereumState;
-use rsp_primitives::account_proof::eip1186_proof_to_account_proof;
-use rsp_rpc_db::RpcDb;
+use rsp_primitives::{account_proof::eip1186_proof_to_account_proof, chain_spec::ChainSpecConfig};
+use rsp_rpc_db::{RpcCache, RpcDb, StateKeys, CACHE_FINALITY_DEPTH, DEFAULT_PREFETCH_CONCURRENCY};
+use tracing::Instrument;
+
+pub mod debug;
+
+pub mod provider;
+
+mod witness;
+pub use witness::ExecutionWitness;
+use witness::ExecutionWitnessDb;
+
+/// Decodes the ABI-encoded [BlockPublicValues] committed by the client programs, making sure they
+/// use the layout version known to this host.
+pub fn decode_public_values(bytes: &[u8]) -> eyre::Result<BlockPublicValues> {
+    let public_values = BlockPublicValues::abi_decode(bytes, true)?;
+    if public_values.version != PUBLIC_VALUES_VERSION {
+        eyre::bail!(
+            "unsupported public values version: expected={}, actual={}",
+            PUBLIC_VALUES_VERSION,
+            public_values.version
+        );
+    }
+
+    Ok(public_values)
+}
 
 /// An executor that fetches data from a [Provider] to execute blocks in the [ClientExecutor].
 #[derive(Debug, Clone)]
 pub struct HostExecutor<T: Transport + Clone, P: Provider<T, AnyNetwork> + Clone> {
     /// The provider which fetches data.
     pub provider: P,
+    /// The chain spec to execute blocks with. If not set, the default spec of the variant is used.
+    pub chain_spec: Option<ChainSpecConfig>,
+    /// The maximum number of in-flight requests issued while prefetching state and fetching
+    /// proofs and headers.
+    pub rpc_concurrency: usize,
+    /// Whether to execute single blocks on top of their `debug_executionWitness` instead of state
+    /// fetched with storage proofs, falling back to the latter if the node doesn't support it.
+    /// Block ranges are rejected when set.
+    pub use_execution_witness: bool,
+    /// The persistent cache consulted before fetching proofs, headers and accounts, for blocks
+    /// at least [CACHE_FINALITY_DEPTH] blocks below the head of the chain.
+    pub cache: Option<RpcCache>,
+    /// Whether to trace the state changes of every transaction when the state root of a block
+    /// doesn't match, and report the first account or storage slot which differs from the chain.
+    pub debug_state_root: bool,
     /// A phantom type to make the struct generic over the transport.
     pub phantom: PhantomData<T>,
 }
@@ -25,7 +77,53 @@ pub struct HostExecutor<T: Transport + Clone, P: Provider<T, AnyNetwork> + Clone
 impl<T: Transport + Clone, P: Provider<T, AnyNetwork> + Clone> HostExecutor<T, P> {
     /// Create a new [`HostExecutor`] with a specific [Provider] and [Transport].
     pub fn new(provider: P) -> Self {
-        Self { provider, phantom: PhantomData }
+        Self {
+            provider,
+            chain_spec: None,
+            rpc_concurrency: DEFAULT_PREFETCH_CONCURRENCY,
+            use_execution_witness: false,
+            cache: None,
+            debug_state_root: false,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Sets a custom chain spec, e.g. one loaded from a genesis file, to execute blocks with.
+    pub fn with_chain_spec(mut self, chain_spec: ChainSpecConfig) -> Self {
+        self.chain_spec = Some(chain_spec);
+        self
+    }
+
+    /// Sets the maximum number of in-flight requests issued while prefetching state and fetching
+    /// proofs and headers.
+    pub fn with_rpc_concurrency(mut self, rpc_concurrency: usize) -> Self {
+        self.rpc_concurrency = rpc_concurrency.max(1);
+        self
+    }
+
+    /// Sets whether to execute single blocks on top of their `debug_executionWitness` instead of
+    /// state fetched with storage proofs.
+    pub fn with_execution_witness(mut self, use_execution_witness: bool) -> Self {
+        self.use_execution_witness = use_execution_witness;
+        self
+    }
+
+    /// Sets the persistent cache consulted before fetching proofs, headers and accounts.
+    pub fn with_cache(mut self, cache: RpcCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Sets whether to trace the state changes of every transaction when the state root of a block
+    /// doesn't match, and report the first account or storage slot which differs from the chain.
+    pub fn with_debug_state_root(mut self, debug_state_root: bool) -> Self {
+        self.debug_state_root = debug_state_root;
+        self
+    }
+
+    /// Returns the chain spec to execute blocks of the given variant with.
+    fn resolve_chain_spec(&self, variant: ChainVariant) -> ChainSpecConfig {
+        self.chain_spec.clone().unwrap_or_else(|| variant.spec())
     }
 
     /// Executes the block with the given block number.
@@ -34,16 +132,287 @@ impl<T: Transport + Clone, P: Provider<T, AnyNetwork> + Clone> HostExecutor<T, P
         block_number: u64,
         variant: ChainVariant,
     ) -> eyre::Result<ClientExecutorInput> {
+        let executor = Self { cache: self.final_cache(block_number).await?, ..self.clone() };
+        let client_input = match variant {
+            ChainVariant::Ethereum | ChainVariant::Sepolia | ChainVariant::Holesky => {
+                executor.execute_variant::<EthereumVariant>(block_number, variant).await
+            }
+            ChainVariant::Optimism | ChainVariant::Base | ChainVariant::OpSepolia => {
+                executor.execute_variant::<OptimismVariant>(block_number, variant).await
+            }
+            ChainVariant::Linea => {
+                executor.execute_variant::<LineaVariant>(block_number, variant).await
+            }
+        }?;
+
+        Ok(client_input)
+    }
+
+    /// Executes the blocks in the inclusive range `[start_block, end_block]` and generates a
+    /// single input covering all of them.
+    pub async fn execute_range(
+        &self,
+        start_block: u64,
+        end_block: u64,
+        variant: ChainVariant,
+    ) -> eyre::Result<ClientExecutorRangeInput> {
+        let executor = Self { cache: self.final_cache(end_block).await?, ..self.clone() };
         let client_input = match variant {
-            ChainVariant::Ethereum => self.execute_variant::<EthereumVariant>(block_number).await,
-            ChainVariant::Optimism => self.execute_variant::<OptimismVariant>(block_number).await,
-            ChainVariant::Linea => self.execute_variant::<LineaVariant>(block_number).await,
+            ChainVariant::Ethereum | ChainVariant::Sepolia | ChainVariant::Holesky => {
+                executor
+                    .execute_range_variant::<EthereumVariant>(start_block, end_block, variant)
+                    .await
+            }
+            ChainVariant::Optimism | ChainVariant::Base | ChainVariant::OpSepolia => {
+                executor
+                    .execute_range_variant::<OptimismVariant>(start_block, end_block, variant)
+                    .await
+            }
+            ChainVariant::Linea => {
+                executor
+                    .execute_range_variant::<LineaVariant>(start_block, end_block, variant)
+                    .await
+            }
         }?;
 
         Ok(client_input)
     }
 
-    async fn execute_variant<V>(&self, block_number: u64) -> eyre::Result<ClientExecutorInput>
+    /// Returns the [RpcCache] to use for the data of the blocks up to `block_number`, if any.
+    ///
+    /// Entries are keyed by height, so the cache is bypassed for blocks less than
+    /// [CACHE_FINALITY_DEPTH] blocks below the head of the chain, which may still be reorged.
+    async fn final_cache(&self, block_number: u64) -> eyre::Result<Option<RpcCache>> {
+        let Some(cache) = &self.cache else {
+            return Ok(None);
+        };
+
+        let head = self.provider.get_block_number().await?;
+        if block_number.saturating_add(CACHE_FINALITY_DEPTH) > head {
+            tracing::info!(
+                "block {} is within {} blocks of the head, bypassing the rpc cache",
+                block_number,
+                CACHE_FINALITY_DEPTH
+            );
+            return Ok(None);
+        }
+
+        Ok(Some(cache.clone()))
+    }
+
+    /// Fetches the account and storage proofs for the given requests, with at most
+    /// [rpc_concurrency](HostExecutor::rpc_concurrency) `eth_getProof` calls in flight. Each
+    /// proof is returned along with the state it was requested for.
+    async fn fetch_proofs(
+        &self,
+        requests: Vec<(ProofState, ProofRequest)>,
+    ) -> eyre::Result<Vec<(ProofState, AccountProof)>> {
+        let span = tracing::info_span!("fetch_proofs", requests = requests.len());
+        async {
+            tracing::info!("fetching storage proofs");
+
+            let total = requests.len();
+            let mut fetched = 0;
+            let proofs = futures::stream::iter(requests)
+                .map(|(tag, request)| async move {
+                    let proof = self.fetch_proof(request).await?;
+                    Ok((tag, proof))
+                })
+                .buffer_unordered(self.rpc_concurrency)
+                .inspect_ok(|_| {
+                    fetched += 1;
+                    tracing::debug!("fetched proof {}/{}", fetched, total);
+                })
+                .try_collect::<Vec<_>>()
+                .await?;
+
+            tracing::info!("fetched {} storage proofs", proofs.len());
+            Ok(proofs)
+        }
+        .instrument(span)
+        .await
+    }
+
+    /// Fetches the proof of an account and the requested storage slots. Storage keys are split
+    /// across multiple `eth_getProof` calls of at most [MAX_PROOF_KEYS] keys each to respect
+    /// provider limits, and their storage proofs merged.
+    async fn fetch_proof(&self, request: ProofRequest) -> eyre::Result<AccountProof> {
+        let ProofRequest { address, keys, block_number } = request;
+        let span = tracing::debug_span!("fetch_proof", %address, keys = keys.len(), block_number);
+        async {
+            if let Some(proof) =
+                self.cache.as_ref().and_then(|cache| cache.get_proof(block_number, address, &keys))
+            {
+                return Ok(eip1186_proof_to_account_proof(proof));
+            }
+
+            let mut chunks = keys.chunks(MAX_PROOF_KEYS);
+            let first_chunk = chunks.next().unwrap_or_default();
+
+            let mut proof = self
+                .provider
+                .get_proof(address, first_chunk.to_vec())
+                .block_id(block_number.into())
+                .await?;
+            for chunk in chunks {
+                let chunk_proof = self
+                    .provider
+                    .get_proof(address, chunk.to_vec())
+                    .block_id(block_number.into())
+                    .await?;
+                proof.storage_proof.extend(chunk_proof.storage_proof);
+            }
+            if let Some(cache) = &self.cache {
+                cache.put_proof(block_number, &keys, &proof);
+            }
+
+            Ok(eip1186_proof_to_account_proof(proof))
+        }
+        .instrument(span)
+        .await
+    }
+
+    /// Fetches the headers of the blocks in `[oldest, newest]`, starting from the most recent,
+    /// with at most [rpc_concurrency](HostExecutor::rpc_concurrency) requests in flight.
+    async fn fetch_ancestor_headers(&self, oldest: u64, newest: u64) -> eyre::Result<Vec<Header>> {
+        let span = tracing::info_span!("fetch_ancestor_headers", oldest, newest);
+        async {
+            tracing::info!("fetching {} ancestor headers", newest - oldest + 1);
+
+            futures::stream::iter((oldest..=newest).rev())
+                .map(|height| async move {
+                    if let Some(header) =
+                        self.cache.as_ref().and_then(|cache| cache.get_header(height))
+                    {
+                        return Ok(header);
+                    }
+
+                    let block = self
+                        .provider
+                        .get_block_by_number(height.into(), false)
+                        .await?
+                        .ok_or(eyre!("couldn't fetch block: {}", height))?;
+                    let hash = block.header.hash;
+                    let header = Header::try_from(block.inner.header)?;
+                    if let Some(cache) = &self.cache {
+                        cache.put_header(hash, &header);
+                    }
+
+                    Ok(header)
+                })
+                .buffered(self.rpc_concurrency)
+                .try_collect::<Vec<_>>()
+                .await
+        }
+        .instrument(span)
+        .await
+    }
+
+    /// Builds the parent state tries of a block from the proofs of the accounts and storage slots
+    /// it touched, before and after its execution.
+    async fn state_from_proofs(
+        &self,
+        block_number: u64,
+        parent_state_root: B256,
+        state_requests: &HashMap<Address, Vec<U256>>,
+        executor_outcome: &ExecutionOutcome,
+    ) -> eyre::Result<EthereumState> {
+        // For every account we touched, fetch the storage proofs for all the slots we touched.
+        let mut proof_requests = Vec::with_capacity(state_requests.len() * 2);
+        for (address, used_keys) in state_requests.iter() {
+            let modified_keys = executor_outcome
+                .state()
+                .state
+                .get(address)
+                .map(|account| {
+                    account.storage.keys().map(|key| B256::from(*key)).collect::<BTreeSet<_>>()
+                })
+                .unwrap_or_default();
+
+            let keys = used_keys
+                .iter()
+                .map(|key| B256::from(*key))
+                .chain(modified_keys.iter().copied())
+                .collect::<BTreeSet<_>>();
+
+            proof_requests
+                .push((ProofState::Parent, ProofRequest::new(*address, keys, block_number - 1)));
+            proof_requests.push((
+                ProofState::AfterBlock(0),
+                ProofRequest::new(*address, modified_keys, block_number),
+            ));
+        }
+
+        let mut before_storage_proofs = HashMap::new();
+        let mut after_storage_proofs = HashMap::new();
+        for (request, proof) in self.fetch_proofs(proof_requests).await? {
+            match request {
+                ProofState::Parent => before_storage_proofs.insert(proof.address, proof),
+                ProofState::AfterBlock(_) => after_storage_proofs.insert(proof.address, proof),
+            };
+        }
+
+        EthereumState::from_transition_proofs(
+            parent_state_root,
+            &before_storage_proofs,
+            &after_storage_proofs,
+        )
+    }
+
+    /// Fetches the execution witness of a block with `debug_executionWitness`.
+    pub async fn fetch_execution_witness(
+        &self,
+        block_number: u64,
+    ) -> eyre::Result<ExecutionWitness> {
+        Ok(self
+            .provider
+            .raw_request("debug_executionWitness".into(), (BlockNumberOrTag::Number(block_number),))
+            .await?)
+    }
+
+    /// Executes a block on top of the parent state read from its `debug_executionWitness`, and
+    /// checks that the witness is sufficient to compute the post state root of the block.
+    ///
+    /// Returns the output of the execution along with the parent state tries, state requests and
+    /// bytecodes of the client input, without any `eth_getProof` call.
+    async fn execute_on_execution_witness<V>(
+        &self,
+        spec: &ChainSpec,
+        block: &BlockWithSenders,
+        parent_state_root: B256,
+        rpc_db: &RpcDb<T, P>,
+    ) -> eyre::Result<(
+        BlockExecutionOutput<Receipt>,
+        EthereumState,
+        HashMap<Address, Vec<U256>>,
+        Vec<Bytecode>,
+    )>
+    where
+        V: Variant,
+    {
+        tracing::info!("fetching the execution witness");
+        let witness = self.fetch_execution_witness(block.header.number).await?;
+
+        tracing::info!("executing the block on {} trie node preimages", witness.state.len());
+        let witness_db = ExecutionWitnessDb::new(&witness, parent_state_root, rpc_db)?;
+        let executor_output =
+            V::execute(spec, block, block.header.difficulty, CacheDB::new(&witness_db))?;
+        let (state, state_requests, bytecodes) = witness_db.into_parts();
+
+        let mut mutated_state = state.clone();
+        mutated_state.update(&HashedPostState::from_bundle_state(&executor_output.state.state))?;
+        if mutated_state.state_root() != block.header.state_root {
+            eyre::bail!("execution witness is incomplete: mismatched state root");
+        }
+
+        Ok((executor_output, state, state_requests, bytecodes))
+    }
+
+    async fn execute_variant<V>(
+        &self,
+        block_number: u64,
+        variant: ChainVariant,
+    ) -> eyre::Result<ClientExecutorInput>
     where
         V: Variant,
     {
@@ -64,25 +433,74 @@ impl<T: Transport + Clone, P: Provider<T, AnyNetwork> + Clone> HostExecutor<T, P
 
         // Setup the spec for the block executor.
         tracing::info!("setting up the spec for the block executor");
-        let spec = V::spec();
+        let chain_spec = self.resolve_chain_spec(variant);
+        let spec = ChainSpec::try_from(&chain_spec)?;
+        chain_spec.check_supported(block_number, current_block.header.timestamp)?;
+
+        // Check that the precompile overrides of the variant match the reference implementations.
+        tracing::info!("checking the precompile overrides");
+        check_active_precompile_overrides(
+            variant,
+            &V::precompile_overrides(),
+            &spec,
+            &current_block.header,
+        )?;
 
         // Setup the database for the block executor.
         tracing::info!("setting up the database for the block executor");
-        let rpc_db = RpcDb::new(self.provider.clone(), block_number - 1);
-        let cache_db = CacheDB::new(&rpc_db);
-
-        // Execute the block and fetch all the necessary data along the way.
-        tracing::info!(
-            "executing the block and with rpc db: block_number={}, transaction_count={}",
-            block_number,
-            current_block.body.len()
-        );
+        let rpc_db = RpcDb::new(self.provider.clone(), block_number - 1)
+            .with_prefetch_concurrency(self.rpc_concurrency);
+        let rpc_db = match &self.cache {
+            Some(cache) => rpc_db.with_cache(cache.clone()),
+            None => rpc_db,
+        };
 
-        let executor_block_input = V::pre_process_block(&current_block)
+        let executor_block_input = V::pre_process_block(&current_block)?
             .with_recovered_senders()
             .ok_or(eyre!("failed to recover senders"))?;
         let executor_difficulty = current_block.header.difficulty;
-        let executor_output = V::execute(&executor_block_input, executor_difficulty, cache_db)?;
+
+        // Execute the block on top of its execution witness if enabled and supported by the node.
+        let witness_execution = if self.use_execution_witness {
+            self.execute_on_execution_witness::<V>(
+                &spec,
+                &executor_block_input,
+                previous_block.state_root,
+                &rpc_db,
+            )
+            .await
+            .inspect_err(|err| {
+                tracing::warn!(
+                    "failed to execute the block on its execution witness, falling back to \
+                     storage proofs: {}",
+                    err
+                )
+            })
+            .ok()
+        } else {
+            None
+        };
+
+        // Otherwise, execute the block and fetch all the necessary data along the way.
+        let (executor_output, witness_state) = match witness_execution {
+            Some((executor_output, state, state_requests, bytecodes)) => {
+                (executor_output, Some((state, state_requests, bytecodes)))
+            }
+            None => {
+                tracing::info!(
+                    "executing the block and with rpc db: block_number={}, transaction_count={}",
+                    block_number,
+                    current_block.body.len()
+                );
+
+                let cache_db = CacheDB::new(&rpc_db);
+                prefetch_block_state::<V, _, _>(&spec, &rpc_db, &executor_block_input, &cache_db)
+                    .await?;
+                let executor_output =
+                    V::execute(&spec, &executor_block_input, executor_difficulty, cache_db)?;
+                (executor_output, None)
+            }
+        };
 
         // Validate the block post execution.
         tracing::info!("validating the block post execution");
@@ -108,62 +526,42 @@ impl<T: Transport + Clone, P: Provider<T, AnyNetwork> + Clone> HostExecutor<T, P
             vec![executor_output.requests.into()],
         );
 
-        let state_requests = rpc_db.get_state_requests();
-
-        // For every account we touched, fetch the storage proofs for all the slots we touched.
-        tracing::info!("fetching storage proofs");
-        let mut before_storage_proofs = Vec::new();
-        let mut after_storage_proofs = Vec::new();
-
-        for (address, used_keys) in state_requests.iter() {
-            let modified_keys = executor_outcome
-                .state()
-                .state
-                .get(address)
-                .map(|account| {
-                    account.storage.keys().map(|key| B256::from(*key)).collect::<BTreeSet<_>>()
-                })
-                .unwrap_or_default()
-                .into_iter()
-                .collect::<Vec<_>>();
-
-            let keys = used_keys
-                .iter()
-                .map(|key| B256::from(*key))
-                .chain(modified_keys.clone().into_iter())
-                .collect::<BTreeSet<_>>()
-                .into_iter()
-                .collect::<Vec<_>>();
-
-            let storage_proof = self
-                .provider
-                .get_proof(*address, keys.clone())
-                .block_id((block_number - 1).into())
-                .await?;
-            before_storage_proofs.push(eip1186_proof_to_account_proof(storage_proof));
-
-            let storage_proof = self
-                .provider
-                .get_proof(*address, modified_keys)
-                .block_id((block_number).into())
-                .await?;
-            after_storage_proofs.push(eip1186_proof_to_account_proof(storage_proof));
-        }
-
-        let state = EthereumState::from_transition_proofs(
-            previous_block.state_root,
-            &before_storage_proofs.iter().map(|item| (item.address, item.clone())).collect(),
-            &after_storage_proofs.iter().map(|item| (item.address, item.clone())).collect(),
-        )?;
+        // Build the parent state tries from storage proofs, unless the block was executed on top of
+        // its execution witness.
+        let (state, state_requests, bytecodes) = match witness_state {
+            Some(witness_state) => witness_state,
+            None => {
+                let state_requests = rpc_db.get_state_requests();
+                let state = self
+                    .state_from_proofs(
+                        block_number,
+                        previous_block.state_root,
+                        &state_requests,
+                        &executor_outcome,
+                    )
+                    .await?;
+                (state, state_requests, rpc_db.get_bytecodes())
+            }
+        };
 
         // Verify the state root.
         tracing::info!("verifying the state root");
         let state_root = {
             let mut mutated_state = state.clone();
-            mutated_state.update(&executor_outcome.hash_state_slow());
+            mutated_state.update(&executor_outcome.hash_state_slow())?;
             mutated_state.state_root()
         };
         if state_root != current_block.state_root {
+            if self.debug_state_root {
+                self.debug_state_root_mismatch::<V>(
+                    variant,
+                    &spec,
+                    &executor_block_input,
+                    &rpc_db,
+                    &executor_outcome,
+                )
+                .await?;
+            }
             eyre::bail!("mismatched state root");
         }
 
@@ -197,23 +595,307 @@ impl<T: Transport + Clone, P: Provider<T, AnyNetwork> + Clone> HostExecutor<T, P
 
         // Fetch the parent headers needed to constrain the BLOCKHASH opcode.
         let oldest_ancestor = *rpc_db.oldest_ancestor.borrow();
-        let mut ancestor_headers = vec![];
-        tracing::info!("fetching {} ancestor headers", block_number - oldest_ancestor);
-        for height in (oldest_ancestor..=(block_number - 1)).rev() {
-            let block = self.provider.get_block_by_number(height.into(), false).await?.unwrap();
-            ancestor_headers.push(block.inner.header.try_into()?);
-        }
+        let ancestor_headers =
+            self.fetch_ancestor_headers(oldest_ancestor, block_number - 1).await?;
 
         // Create the client input.
         let client_input = ClientExecutorInput {
-            current_block: V::pre_process_block(&current_block),
+            chain_spec,
+            current_block,
             ancestor_headers,
             parent_state: state,
             state_requests,
-            bytecodes: rpc_db.get_bytecodes(),
+            bytecodes,
         };
         tracing::info!("successfully generated client input");
 
         Ok(client_input)
     }
+
+    async fn execute_range_variant<V>(
+        &self,
+        start_block: u64,
+        end_block: u64,
+        variant: ChainVariant,
+    ) -> eyre::Result<ClientExecutorRangeInput>
+    where
+        V: Variant,
+    {
+        if start_block == 0 || end_block < start_block {
+            eyre::bail!("invalid block range: {}..={}", start_block, end_block);
+        }
+        if self.use_execution_witness {
+            eyre::bail!("execution witnesses are not supported for block ranges");
+        }
+
+        // Fetch the blocks in the range and the block preceding it from the provider.
+        tracing::info!("fetching blocks {}..={} and the previous block", start_block, end_block);
+        let previous_block = self
+            .provider
+            .get_block_by_number((start_block - 1).into(), true)
+            .await?
+            .map(|block| Block::try_from(block.inner))
+            .ok_or(eyre!("couldn't fetch block: {}", start_block - 1))??;
+        let blocks = futures::stream::iter(start_block..=end_block)
+            .map(|block_number| async move {
+                let block = self
+                    .provider
+                    .get_block_by_number(block_number.into(), true)
+                    .await?
+                    .map(|block| Block::try_from(block.inner))
+                    .ok_or(eyre!("couldn't fetch block: {}", block_number))??;
+                Ok(block)
+            })
+            .buffered(self.rpc_concurrency)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        // Setup the spec for the block executor.
+        let chain_spec = self.resolve_chain_spec(variant);
+        let spec = ChainSpec::try_from(&chain_spec)?;
+        for block in blocks.iter() {
+            chain_spec.check_supported(block.header.number, block.header.timestamp)?;
+        }
+
+        // Setup a single database for the whole range, so that the state fetched for earlier
+        // blocks is reused, and the changes made by earlier blocks are visible to later ones.
+        tracing::info!("setting up the database for the block executor");
+        let rpc_db = RpcDb::new(self.provider.clone(), start_block - 1)
+            .with_prefetch_concurrency(self.rpc_concurrency);
+        let rpc_db = match &self.cache {
+            Some(cache) => rpc_db.with_cache(cache.clone()),
+            None => rpc_db,
+        };
+        let mut cache_db = CacheDB::new(&rpc_db);
+
+        let mut executor_outcomes = Vec::with_capacity(blocks.len());
+        // The precompile overrides of the variant are checked against the reference implementations
+        // at the spec of every block.
+        let precompile_overrides = V::precompile_overrides();
+        for block in blocks.iter() {
+            tracing::info!(
+                "executing the block and with rpc db: block_number={}, transaction_count={}",
+                block.header.number,
+                block.body.len()
+            );
+
+            check_active_precompile_overrides(
+                variant,
+                &precompile_overrides,
+                &spec,
+                &block.header,
+            )?;
+
+            let executor_block_input = V::pre_process_block(block)?
+                .with_recovered_senders()
+                .ok_or(eyre!("failed to recover senders"))?;
+            let executor_difficulty = block.header.difficulty;
+            prefetch_block_state::<V, _, _>(&spec, &rpc_db, &executor_block_input, &cache_db)
+                .await?;
+            let executor_output =
+                V::execute(&spec, &executor_block_input, executor_difficulty, &mut cache_db)?;
+
+            // Validate the block post execution.
+            V::validate_block_post_execution(
+                &executor_block_input,
+                &spec,
+                &executor_output.receipts,
+                &executor_output.requests,
+            )?;
+
+            // Make the changes of this block visible to the next one.
+            commit_bundle_state(&mut cache_db, &executor_output.state);
+
+            executor_outcomes.push(ExecutionOutcome::new(
+                executor_output.state,
+                Receipts::from(executor_output.receipts),
+                block.header.number,
+                vec![executor_output.requests.into()],
+            ));
+        }
+
+        let state_requests = rpc_db.get_state_requests();
+
+        // For every account we touched, fetch the storage proofs for all the slots we touched
+        // before the range, and the proofs for the slots modified by each block that changed the
+        // account after it.
+        let mut proof_requests = Vec::with_capacity(state_requests.len() * 2);
+        for (address, used_keys) in state_requests.iter() {
+            let modified_keys_by_block = executor_outcomes
+                .iter()
+                .enumerate()
+                .filter_map(|(index, outcome)| {
+                    let account = outcome.state().state.get(address)?;
+                    let modified_keys =
+                        account.storage.keys().map(|key| B256::from(*key)).collect::<BTreeSet<_>>();
+                    Some((index, outcome.first_block(), modified_keys))
+                })
+                .collect::<Vec<_>>();
+
+            let keys = used_keys
+                .iter()
+                .map(|key| B256::from(*key))
+                .chain(modified_keys_by_block.iter().flat_map(|(_, _, keys)| keys.iter().copied()))
+                .collect::<BTreeSet<_>>();
+            proof_requests
+                .push((ProofState::Parent, ProofRequest::new(*address, keys, start_block - 1)));
+
+            for (index, block_number, modified_keys) in modified_keys_by_block {
+                proof_requests.push((
+                    ProofState::AfterBlock(index),
+                    ProofRequest::new(*address, modified_keys, block_number),
+                ));
+            }
+        }
+
+        let mut before_storage_proofs = HashMap::new();
+        let mut after_storage_proofs = vec![HashMap::new(); executor_outcomes.len()];
+        for (request, proof) in self.fetch_proofs(proof_requests).await? {
+            match request {
+                ProofState::Parent => before_storage_proofs.insert(proof.address, proof),
+                ProofState::AfterBlock(index) => {
+                    after_storage_proofs[index].insert(proof.address, proof)
+                }
+            };
+        }
+
+        let state = EthereumState::from_multi_transition_proofs(
+            previous_block.state_root,
+            &before_storage_proofs,
+            &after_storage_proofs,
+        )?;
+
+        // Verify the state root of every block in the range.
+        tracing::info!("verifying the state roots");
+        let mut mutated_state = state.clone();
+        for (block, outcome) in blocks.iter().zip(executor_outcomes.iter()) {
+            mutated_state.update(&outcome.hash_state_slow())?;
+            if mutated_state.state_root() != block.state_root {
+                eyre::bail!("mismatched state root at block {}", block.header.number);
+            }
+        }
+
+        // Fetch the parent headers needed to constrain the BLOCKHASH opcode.
+        let oldest_ancestor = *rpc_db.oldest_ancestor.borrow();
+        let ancestor_headers =
+            self.fetch_ancestor_headers(oldest_ancestor, start_block - 1).await?;
+
+        // Create the client input.
+        let client_input = ClientExecutorRangeInput {
+            chain_spec,
+            blocks,
+            ancestor_headers,
+            parent_state: state,
+            state_requests,
+            bytecodes: rpc_db.get_bytecodes(),
+        };
+        tracing::info!(
+            "successfully generated client input for blocks {}..={}",
+            start_block,
+            end_block
+        );
+
+        Ok(client_input)
+    }
+}
+
+/// The maximum number of speculative execution passes run to discover the state touched by a
+/// block before executing it for real.
+const MAX_SPECULATIVE_PASSES: usize = 3;
+
+/// Fetches the state touched by a block into the [RpcDb] ahead of its execution.
+///
+/// The keys are first collected from the transaction access lists and the accounts known to be
+/// involved in the block. The block is then speculatively executed on top of `cache_db` to
+/// discover the remaining keys, until a pass reads nothing that hasn't been fetched yet. Keys
+/// only read by a speculative pass are harmless: they merely end up in the witness.
+async fn prefetch_block_state<V, T, P>(
+    spec: &ChainSpec,
+    rpc_db: &RpcDb<T, P>,
+    block: &BlockWithSenders,
+    cache_db: &CacheDB<&RpcDb<T, P>>,
+) -> eyre::Result<()>
+where
+    V: Variant,
+    T: Transport + Clone,
+    P: Provider<T, AnyNetwork> + Clone,
+{
+    let mut keys = StateKeys::default();
+    keys.accounts.insert(block.header.beneficiary);
+    keys.accounts.extend(block.senders.iter().copied());
+    for transaction in block.body.iter() {
+        if let Some(to) = transaction.to() {
+            keys.accounts.insert(to);
+        }
+        for item in transaction.access_list().into_iter().flat_map(|list| list.iter()) {
+            keys.accounts.insert(item.address);
+            keys.storage.extend(
+                item.storage_keys.iter().map(|key| (item.address, U256::from_be_bytes(key.0))),
+            );
+        }
+    }
+    if let Some(withdrawals) = &block.withdrawals {
+        keys.accounts.extend(withdrawals.iter().map(|withdrawal| withdrawal.address));
+    }
+
+    // The speculative passes run on a single layer on top of `cache_db`, which is left untouched
+    // for the actual execution.
+    let mut speculative_db = CacheDB::new(cache_db);
+    for pass in 0..MAX_SPECULATIVE_PASSES {
+        rpc_db.prefetch(&keys).await?;
+
+        let (_, missed_keys) = rpc_db
+            .speculate(|| V::execute(spec, block, block.header.difficulty, &mut speculative_db));
+        tracing::debug!(
+            "speculative pass {} missed {} accounts and {} storage slots",
+            pass,
+            missed_keys.accounts.len(),
+            missed_keys.storage.len()
+        );
+        if missed_keys.is_empty() {
+            break;
+        }
+
+        // Forget the placeholder values read for the missed keys, so that the next pass reads the
+        // prefetched ones instead.
+        for address in &missed_keys.accounts {
+            speculative_db.accounts.remove(address);
+        }
+        for (address, index) in &missed_keys.storage {
+            if let Some(account) = speculative_db.accounts.get_mut(address) {
+                account.storage.remove(index);
+            }
+        }
+
+        keys = missed_keys;
+    }
+
+    Ok(())
+}
+
+/// The maximum number of storage keys requested in a single `eth_getProof` call.
+const MAX_PROOF_KEYS: usize = 500;
+
+/// The state a [ProofRequest] is made against, telling apart the proofs fetched by
+/// [HostExecutor::fetch_proofs].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProofState {
+    /// The parent state of the first executed block.
+    Parent,
+    /// The state after the execution of the block at the given index among the executed blocks.
+    AfterBlock(usize),
+}
+
+/// A request for the proof of an account and some of its storage slots at a block.
+#[derive(Debug)]
+struct ProofRequest {
+    address: Address,
+    keys: Vec<B256>,
+    block_number: u64,
+}
+
+impl ProofRequest {
+    fn new(address: Address, keys: BTreeSet<B256>, block_number: u64) -> Self {
+        Self { address, keys: keys.into_iter().collect(), block_number }
+    }
 }
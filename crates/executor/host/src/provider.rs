@@ -0,0 +1,482 @@
+//! A resilient transport for RPC providers.
+//!
+//! The [RetryTransport] wraps one or more endpoints, HTTP ones by default. Requests that fail with
+//! a transient error (rate limiting, server errors, timeouts) are retried with exponential backoff,
+//! switching to the next endpoint on every failure, and an optional requests-per-second budget is
+//! enforced across all of them. Every call of a batch counts towards the budget, and only the calls
+//! of a batch that were rate limited are retried. Since it is a regular
+//! [Transport](alloy_transport::Transport), the resulting [RetryProvider] can be used by both the
+//! [HostExecutor](crate::HostExecutor) and the [RpcDb](rsp_rpc_db::RpcDb).
+
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use alloy_json_rpc::{RequestPacket, Response, ResponsePacket, ResponsePayload, RpcError};
+use alloy_provider::{network::AnyNetwork, RootProvider};
+use alloy_rpc_client::RpcClient;
+use alloy_transport::{TransportError, TransportErrorKind, TransportFut};
+use alloy_transport_http::{reqwest, Http};
+use tokio::time::Instant;
+use tower::Service;
+use url::Url;
+
+/// A provider over a [RetryTransport].
+pub type RetryProvider = RootProvider<RetryTransport, AnyNetwork>;
+
+/// JSON-RPC error code used by some providers to signal that a rate limit has been exceeded.
+const LIMIT_EXCEEDED_ERROR_CODE: i64 = -32005;
+
+/// Configuration of the [RetryTransport].
+#[derive(Debug, Clone)]
+pub struct RetryConfig {
+    /// The maximum number of times a request is retried after a transient error.
+    pub max_retries: u32,
+    /// The delay before the first retry, doubled after every subsequent failure.
+    pub initial_backoff: Duration,
+    /// The maximum delay between two retries.
+    pub max_backoff: Duration,
+    /// The maximum number of requests sent per second across all endpoints, if any.
+    pub requests_per_second: Option<u32>,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(10),
+            requests_per_second: None,
+        }
+    }
+}
+
+/// A transport that retries transient failures with exponential backoff, fails over across a list
+/// of endpoints and enforces a requests-per-second budget.
+#[derive(Debug, Clone)]
+pub struct RetryTransport<S = Http<reqwest::Client>> {
+    inner: Arc<RetryTransportInner<S>>,
+}
+
+#[derive(Debug)]
+struct RetryTransportInner<S> {
+    /// The endpoints, in order of preference.
+    transports: Vec<S>,
+    /// The index of the endpoint currently in use.
+    active: AtomicUsize,
+    /// The retry configuration.
+    config: RetryConfig,
+    /// The time at which the next request is allowed to be sent, if rate limited.
+    next_request_at: Mutex<Instant>,
+}
+
+impl RetryTransport {
+    /// Creates a new [RetryTransport] over the given HTTP endpoints, which are tried in order.
+    pub fn new(urls: Vec<Url>, config: RetryConfig) -> eyre::Result<Self> {
+        let client = reqwest::Client::new();
+        Self::with_transports(
+            urls.into_iter().map(|url| Http::with_client(client.clone(), url)).collect(),
+            config,
+        )
+    }
+
+    /// Creates a [RetryProvider] over the given endpoints.
+    pub fn provider(urls: Vec<Url>, config: RetryConfig) -> eyre::Result<RetryProvider> {
+        Ok(RootProvider::new(RpcClient::new(Self::new(urls, config)?, false)))
+    }
+}
+
+impl<S> RetryTransport<S>
+where
+    S: Service<
+            RequestPacket,
+            Response = ResponsePacket,
+            Error = TransportError,
+            Future = TransportFut<'static>,
+        > + Clone
+        + Send
+        + Sync
+        + 'static,
+{
+    /// Creates a new [RetryTransport] over the given endpoints, which are tried in order.
+    pub fn with_transports(transports: Vec<S>, config: RetryConfig) -> eyre::Result<Self> {
+        if transports.is_empty() {
+            eyre::bail!("at least one RPC URL is required");
+        }
+
+        Ok(Self {
+            inner: Arc::new(RetryTransportInner {
+                transports,
+                active: AtomicUsize::new(0),
+                config,
+                next_request_at: Mutex::new(Instant::now()),
+            }),
+        })
+    }
+
+    async fn send(self, request: RequestPacket) -> Result<ResponsePacket, TransportError> {
+        let inner = &self.inner;
+        let mut backoff = inner.config.initial_backoff;
+        let mut attempt = 0;
+
+        // The calls still to be sent, and the responses already received for the others.
+        let mut pending = request;
+        let mut responses = Vec::new();
+
+        loop {
+            self.wait_for_budget(pending.len()).await;
+
+            let index = inner.active.load(Ordering::Relaxed);
+            let mut transport = inner.transports[index].clone();
+            let result = transport.call(pending.clone()).await;
+
+            let retryable = match &result {
+                Ok(response) => is_rate_limited_response(response),
+                Err(error) => is_retryable_error(error),
+            };
+            if !retryable || attempt >= inner.config.max_retries {
+                return match result {
+                    Ok(ResponsePacket::Batch(batch)) if !responses.is_empty() => {
+                        responses.extend(batch);
+                        Ok(ResponsePacket::Batch(responses))
+                    }
+                    // Return the responses already received for a batch, leaving the calls which
+                    // got none to fail on their own.
+                    Err(error) if !responses.is_empty() => {
+                        tracing::warn!(
+                            "RPC batch partly answered, {} calls failed: {}",
+                            pending.len(),
+                            error
+                        );
+                        Ok(ResponsePacket::Batch(responses))
+                    }
+                    result => result,
+                };
+            }
+
+            attempt += 1;
+            let next = (index + 1) % inner.transports.len();
+            let _ =
+                inner.active.compare_exchange(index, next, Ordering::Relaxed, Ordering::Relaxed);
+
+            tracing::warn!(
+                "transient RPC failure, retrying in {:?} (attempt {}/{}): {}",
+                backoff,
+                attempt,
+                inner.config.max_retries,
+                match &result {
+                    Ok(_) => "rate limited".to_string(),
+                    Err(error) => error.to_string(),
+                }
+            );
+
+            // Only retry the calls of a batch which were rate limited, keeping the other responses.
+            if let (RequestPacket::Batch(requests), Ok(ResponsePacket::Batch(batch))) =
+                (&pending, result)
+            {
+                let (limited, succeeded): (Vec<_>, Vec<_>) =
+                    batch.into_iter().partition(is_rate_limited);
+                responses.extend(succeeded);
+
+                let limited =
+                    limited.into_iter().map(|response| response.id).collect::<HashSet<_>>();
+                pending = RequestPacket::Batch(
+                    requests
+                        .iter()
+                        .filter(|request| limited.contains(request.id()))
+                        .cloned()
+                        .collect(),
+                );
+            }
+
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(inner.config.max_backoff);
+        }
+    }
+
+    /// Waits until sending another request with the given number of calls fits within the
+    /// requests-per-second budget, which is charged for every call of a batch.
+    async fn wait_for_budget(&self, calls: usize) {
+        let Some(requests_per_second) = self.inner.config.requests_per_second else {
+            return;
+        };
+        let interval = Duration::from_secs(1) / requests_per_second.max(1);
+
+        let delay = {
+            let mut next_request_at = self.inner.next_request_at.lock().unwrap();
+            let now = Instant::now();
+            let slot = (*next_request_at).max(now);
+            *next_request_at = slot + interval * calls.max(1) as u32;
+            slot - now
+        };
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+impl<S> Service<RequestPacket> for RetryTransport<S>
+where
+    S: Service<
+            RequestPacket,
+            Response = ResponsePacket,
+            Error = TransportError,
+            Future = TransportFut<'static>,
+        > + Clone
+        + Send
+        + Sync
+        + 'static,
+{
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        Box::pin(self.clone().send(request))
+    }
+}
+
+/// Returns whether a transport error is transient: rate limiting, server errors, timeouts and
+/// connection failures.
+fn is_retryable_error(error: &TransportError) -> bool {
+    match error {
+        RpcError::Transport(TransportErrorKind::HttpError(error)) => {
+            error.status == 429 || (500..600).contains(&error.status)
+        }
+        RpcError::Transport(TransportErrorKind::Custom(error)) => error
+            .downcast_ref::<reqwest::Error>()
+            .is_some_and(|error| error.is_timeout() || error.is_connect()),
+        RpcError::Transport(TransportErrorKind::MissingBatchResponse(_)) => true,
+        RpcError::ErrorResp(payload) => is_rate_limit_error(payload.code, &payload.message),
+        _ => false,
+    }
+}
+
+/// Returns whether any of the responses in the packet is a rate limiting error.
+fn is_rate_limited_response(response: &ResponsePacket) -> bool {
+    match response {
+        ResponsePacket::Single(response) => is_rate_limited(response),
+        ResponsePacket::Batch(responses) => responses.iter().any(is_rate_limited),
+    }
+}
+
+/// Returns whether a response is a rate limiting error.
+fn is_rate_limited(response: &Response) -> bool {
+    match &response.payload {
+        ResponsePayload::Failure(error) => is_rate_limit_error(error.code, &error.message),
+        ResponsePayload::Success(_) => false,
+    }
+}
+
+fn is_rate_limit_error(code: i64, message: &str) -> bool {
+    code == 429 ||
+        code == LIMIT_EXCEEDED_ERROR_CODE ||
+        message.to_lowercase().contains("rate limit")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use alloy_json_rpc::{Id, Request};
+
+    use super::*;
+
+    /// A scripted endpoint, recording the ids of the calls it receives.
+    #[derive(Debug, Clone)]
+    struct MockTransport {
+        index: usize,
+        state: Arc<Mutex<MockState>>,
+    }
+
+    #[derive(Debug, Default)]
+    struct MockState {
+        /// The results to return, in order, across all endpoints.
+        results: VecDeque<Result<ResponsePacket, TransportError>>,
+        /// The index of the endpoint and the ids of the calls of every request received.
+        calls: Vec<(usize, Vec<Id>)>,
+    }
+
+    impl Service<RequestPacket> for MockTransport {
+        type Response = ResponsePacket;
+        type Error = TransportError;
+        type Future = TransportFut<'static>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: RequestPacket) -> Self::Future {
+            let ids = match &request {
+                RequestPacket::Single(request) => vec![request.id().clone()],
+                RequestPacket::Batch(requests) => {
+                    requests.iter().map(|request| request.id().clone()).collect()
+                }
+            };
+            let mut state = self.state.lock().unwrap();
+            state.calls.push((self.index, ids));
+            let result = state.results.pop_front().expect("unexpected call");
+            Box::pin(async move { result })
+        }
+    }
+
+    fn mock_transport(
+        endpoints: usize,
+        config: RetryConfig,
+        results: Vec<Result<ResponsePacket, TransportError>>,
+    ) -> (RetryTransport<MockTransport>, Arc<Mutex<MockState>>) {
+        let state =
+            Arc::new(Mutex::new(MockState { results: results.into(), ..Default::default() }));
+        let transports =
+            (0..endpoints).map(|index| MockTransport { index, state: state.clone() }).collect();
+        (RetryTransport::with_transports(transports, config).unwrap(), state)
+    }
+
+    fn request(id: u64) -> RequestPacket {
+        RequestPacket::Single(batch_request(id))
+    }
+
+    fn batch(ids: &[u64]) -> RequestPacket {
+        RequestPacket::Batch(ids.iter().map(|id| batch_request(*id)).collect())
+    }
+
+    fn batch_request(id: u64) -> alloy_json_rpc::SerializedRequest {
+        Request::new("eth_blockNumber", Id::Number(id), ()).serialize().unwrap()
+    }
+
+    fn success(id: u64) -> Response {
+        serde_json::from_str(&format!(r#"{{"jsonrpc":"2.0","id":{},"result":"0x1"}}"#, id)).unwrap()
+    }
+
+    fn rate_limited(id: u64) -> Response {
+        serde_json::from_str(&format!(
+            r#"{{"jsonrpc":"2.0","id":{},"error":{{"code":429,"message":"rate limited"}}}}"#,
+            id
+        ))
+        .unwrap()
+    }
+
+    fn server_error() -> Result<ResponsePacket, TransportError> {
+        Err(TransportErrorKind::http_error(503, String::new()))
+    }
+
+    fn response_ids(response: &ResponsePacket) -> Vec<Id> {
+        match response {
+            ResponsePacket::Single(response) => vec![response.id.clone()],
+            ResponsePacket::Batch(responses) => {
+                responses.iter().map(|response| response.id.clone()).collect()
+            }
+        }
+    }
+
+    fn ids(ids: &[u64]) -> Vec<Id> {
+        ids.iter().map(|id| Id::Number(*id)).collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_failover_rotation() {
+        let (mut transport, state) = mock_transport(
+            3,
+            RetryConfig::default(),
+            vec![
+                server_error(),
+                server_error(),
+                server_error(),
+                Ok(ResponsePacket::Single(success(0))),
+                Ok(ResponsePacket::Single(success(1))),
+            ],
+        );
+
+        transport.call(request(0)).await.unwrap();
+        // The endpoint that last succeeded keeps being used.
+        transport.call(request(1)).await.unwrap();
+
+        let endpoints =
+            state.lock().unwrap().calls.iter().map(|(index, _)| *index).collect::<Vec<_>>();
+        assert_eq!(endpoints, vec![0, 1, 2, 0, 0]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_backoff_capped() {
+        let config = RetryConfig {
+            max_retries: 4,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(250),
+            requests_per_second: None,
+        };
+        let (mut transport, state) =
+            mock_transport(1, config, (0..5).map(|_| server_error()).collect());
+
+        let start = Instant::now();
+        assert!(transport.call(request(0)).await.is_err());
+
+        // The delays are 100ms, 200ms, then capped at 250ms.
+        assert_eq!(start.elapsed(), Duration::from_millis(100 + 200 + 250 + 250));
+        assert_eq!(state.lock().unwrap().calls.len(), 5);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_batch_retries_rate_limited_calls() {
+        let (mut transport, state) = mock_transport(
+            1,
+            RetryConfig::default(),
+            vec![
+                Ok(ResponsePacket::Batch(vec![success(0), rate_limited(1), success(2)])),
+                Ok(ResponsePacket::Batch(vec![success(1)])),
+            ],
+        );
+
+        let response = transport.call(batch(&[0, 1, 2])).await.unwrap();
+        assert_eq!(response_ids(&response), ids(&[0, 2, 1]));
+
+        let calls = state.lock().unwrap().calls.clone();
+        assert_eq!(calls, vec![(0, ids(&[0, 1, 2])), (0, ids(&[1]))]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_batch_partly_answered() {
+        let (mut transport, _) = mock_transport(
+            1,
+            RetryConfig::default(),
+            vec![
+                Ok(ResponsePacket::Batch(vec![success(0), rate_limited(1)])),
+                Err(TransportErrorKind::custom_str("connection reset")),
+            ],
+        );
+
+        // The response received before the failure is kept.
+        let response = transport.call(batch(&[0, 1])).await.unwrap();
+        assert_eq!(response_ids(&response), ids(&[0]));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_budget_charged_per_call() {
+        let config = RetryConfig { requests_per_second: Some(10), ..Default::default() };
+        let (mut transport, _) = mock_transport(
+            1,
+            config,
+            vec![
+                Ok(ResponsePacket::Batch((0..5).map(success).collect())),
+                Ok(ResponsePacket::Single(success(5))),
+            ],
+        );
+
+        let start = Instant::now();
+        transport.call(batch(&[0, 1, 2, 3, 4])).await.unwrap();
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        // The batch used the budget of 5 calls, at 100ms each.
+        transport.call(request(5)).await.unwrap();
+        assert_eq!(start.elapsed(), Duration::from_millis(500));
+    }
+}
@@ -0,0 +1,184 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet, HashMap},
+};
+
+use alloy_provider::{network::AnyNetwork, Provider};
+use alloy_transport::Transport;
+use reth_primitives::{
+    keccak256,
+    revm_primitives::{db::DatabaseRef, AccountInfo, Bytecode},
+    Address, Bytes, B256, KECCAK_EMPTY, U256,
+};
+use reth_storage_errors::{db::DatabaseError, provider::ProviderError};
+use rsp_mpt::{EthereumState, TrieNodes};
+use rsp_rpc_db::RpcDb;
+use serde::{Deserialize, Deserializer};
+
+/// The response of the `debug_executionWitness` RPC method, containing the data needed to
+/// statelessly execute a block on top of its parent state.
+///
+/// Nodes disagree on whether preimages are returned as lists or as maps keyed by their hashes, so
+/// both forms are accepted.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ExecutionWitness {
+    /// The RLP-encoded nodes of the state trie and the storage tries touched by the block.
+    #[serde(deserialize_with = "deserialize_preimages")]
+    pub state: Vec<Bytes>,
+    /// The bytecodes of the contracts touched by the block.
+    #[serde(default, deserialize_with = "deserialize_preimages")]
+    pub codes: Vec<Bytes>,
+}
+
+fn deserialize_preimages<'de, D>(deserializer: D) -> Result<Vec<Bytes>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Preimages {
+        List(Vec<Bytes>),
+        Map(HashMap<B256, Bytes>),
+    }
+
+    Ok(match Preimages::deserialize(deserializer)? {
+        Preimages::List(preimages) => preimages,
+        Preimages::Map(preimages) => preimages.into_values().collect(),
+    })
+}
+
+/// Errors that can occur when reading the parent state of a block from its [ExecutionWitness].
+#[derive(Debug, Clone, thiserror::Error)]
+pub(crate) enum ExecutionWitnessDbError {
+    #[error("missing trie nodes in execution witness: {0}")]
+    MissingTrieNodes(String),
+    #[error("missing bytecode in execution witness: {0}")]
+    MissingBytecode(B256),
+}
+
+impl From<ExecutionWitnessDbError> for ProviderError {
+    fn from(error: ExecutionWitnessDbError) -> Self {
+        ProviderError::Database(DatabaseError::Other(error.to_string()))
+    }
+}
+
+/// A database reading the parent state of a block from its [ExecutionWitness], and recording the
+/// accounts and storage slots read during execution.
+///
+/// Block hashes are not part of the witness, so they are read from an [RpcDb] instead.
+pub(crate) struct ExecutionWitnessDb<'a, T, P> {
+    /// The trie nodes of the witness.
+    nodes: TrieNodes,
+    /// The bytecodes of the witness, indexed by their hash.
+    codes: HashMap<B256, Bytes>,
+    /// The parent state, whose storage tries are resolved as accounts are read.
+    state: RefCell<EthereumState>,
+    /// The storage slots read, indexed by the address of the accounts read.
+    state_requests: RefCell<BTreeMap<Address, BTreeSet<U256>>>,
+    /// The bytecodes of the accounts read, indexed by their hash.
+    bytecodes: RefCell<BTreeMap<B256, Bytecode>>,
+    /// The database which fetches block hashes.
+    rpc_db: &'a RpcDb<T, P>,
+}
+
+impl<'a, T, P> ExecutionWitnessDb<'a, T, P>
+where
+    T: Transport + Clone,
+    P: Provider<T, AnyNetwork> + Clone,
+{
+    /// Creates a new [ExecutionWitnessDb] reading the state with the given root from the witness.
+    pub(crate) fn new(
+        witness: &ExecutionWitness,
+        state_root: B256,
+        rpc_db: &'a RpcDb<T, P>,
+    ) -> eyre::Result<Self> {
+        let nodes = TrieNodes::from_preimages(&witness.state)?;
+        let state = nodes.state(state_root);
+        let codes = witness.codes.iter().map(|code| (keccak256(code), code.clone())).collect();
+
+        Ok(Self {
+            nodes,
+            codes,
+            state: RefCell::new(state),
+            state_requests: RefCell::default(),
+            bytecodes: RefCell::default(),
+            rpc_db,
+        })
+    }
+
+    /// Consumes the database, returning the parent state tries of the accounts read, along with
+    /// the state requests and bytecodes the client needs to read them.
+    pub(crate) fn into_parts(
+        self,
+    ) -> (EthereumState, revm_primitives::HashMap<Address, Vec<U256>>, Vec<Bytecode>) {
+        let state_requests = self
+            .state_requests
+            .into_inner()
+            .into_iter()
+            .map(|(address, slots)| (address, slots.into_iter().collect()))
+            .collect();
+        let bytecodes = self.bytecodes.into_inner().into_values().collect();
+
+        (self.state.into_inner(), state_requests, bytecodes)
+    }
+}
+
+impl<T, P> DatabaseRef for ExecutionWitnessDb<'_, T, P>
+where
+    T: Transport + Clone,
+    P: Provider<T, AnyNetwork> + Clone,
+{
+    type Error = ProviderError;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        let account = self
+            .nodes
+            .resolve_account(&mut self.state.borrow_mut(), &address)
+            .map_err(|e| ExecutionWitnessDbError::MissingTrieNodes(e.to_string()))?;
+        self.state_requests.borrow_mut().entry(address).or_default();
+
+        // Like the client, absent accounts are loaded as empty ones.
+        let Some(account) = account else {
+            return Ok(Some(AccountInfo::default()));
+        };
+
+        let bytecode = self.code_by_hash_ref(account.code_hash)?;
+        self.bytecodes.borrow_mut().insert(account.code_hash, bytecode.clone());
+
+        Ok(Some(AccountInfo {
+            balance: account.balance,
+            nonce: account.nonce,
+            code_hash: account.code_hash,
+            code: Some(bytecode),
+        }))
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        if code_hash == KECCAK_EMPTY {
+            return Ok(Bytecode::new_raw(Bytes::new()));
+        }
+
+        let code = self
+            .codes
+            .get(&code_hash)
+            .ok_or(ExecutionWitnessDbError::MissingBytecode(code_hash))?;
+        Ok(Bytecode::new_raw(code.clone()))
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        let mut state = self.state.borrow_mut();
+        self.nodes
+            .resolve_account(&mut state, &address)
+            .map_err(|e| ExecutionWitnessDbError::MissingTrieNodes(e.to_string()))?;
+        self.state_requests.borrow_mut().entry(address).or_default().insert(index);
+
+        let value = state.storage_tries[&keccak256(address)]
+            .get_rlp::<U256>(keccak256(index.to_be_bytes::<32>()).as_slice())
+            .map_err(|e| ExecutionWitnessDbError::MissingTrieNodes(e.to_string()))?;
+        Ok(value.unwrap_or_default())
+    }
+
+    fn block_hash_ref(&self, number: u64) -> Result<B256, Self::Error> {
+        self.rpc_db.block_hash_ref(number)
+    }
+}
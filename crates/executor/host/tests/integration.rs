@@ -1,9 +1,11 @@
 use alloy_provider::ReqwestProvider;
+use alloy_sol_types::SolValue;
 use rsp_client_executor::{
-    io::ClientExecutorInput, ChainVariant, ClientExecutor, EthereumVariant, LineaVariant,
-    OptimismVariant, Variant,
+    error::ClientError,
+    io::{ClientExecutorInput, ClientExecutorRangeInput},
+    ChainVariant, ClientExecutor, EthereumVariant, LineaVariant, OptimismVariant, Variant,
 };
-use rsp_host_executor::HostExecutor;
+use rsp_host_executor::{decode_public_values, HostExecutor};
 use tracing_subscriber::{
     filter::EnvFilter, fmt, prelude::__tracing_subscriber_SubscriberExt, util::SubscriberInitExt,
 };
@@ -19,11 +21,122 @@ async fn test_e2e_optimism() {
     run_e2e::<OptimismVariant>(ChainVariant::Optimism, "RPC_10", 122853660).await;
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn test_e2e_sepolia() {
+    run_e2e::<EthereumVariant>(ChainVariant::Sepolia, "RPC_11155111", 6500000).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_e2e_holesky() {
+    run_e2e::<EthereumVariant>(ChainVariant::Holesky, "RPC_17000", 2200000).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_e2e_base() {
+    run_e2e::<OptimismVariant>(ChainVariant::Base, "RPC_8453", 19000000).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_e2e_op_sepolia() {
+    run_e2e::<OptimismVariant>(ChainVariant::OpSepolia, "RPC_11155420", 16000000).await;
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn test_e2e_linea() {
     run_e2e::<LineaVariant>(ChainVariant::Linea, "RPC_59144", 5600000).await;
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn test_e2e_ethereum_range() {
+    // Intialize the environment variables.
+    dotenv::dotenv().ok();
+
+    // Setup the provider.
+    let rpc_url = Url::parse(std::env::var("RPC_1").unwrap().as_str()).expect("invalid rpc url");
+    let provider = ReqwestProvider::new_http(rpc_url);
+
+    // Setup the host executor.
+    let host_executor = HostExecutor::new(provider);
+
+    // Execute the host.
+    let client_input = host_executor
+        .execute_range(18884864, 18884865, ChainVariant::Ethereum)
+        .await
+        .expect("failed to execute host");
+
+    // Execute the client.
+    let output = ClientExecutor
+        .execute_range::<EthereumVariant>(client_input.clone())
+        .expect("failed to execute client");
+    assert_eq!(output.end_block_hash, client_input.blocks[1].header.hash_slow());
+
+    // Save the client input to a buffer.
+    let buffer = bincode::serialize(&client_input).unwrap();
+
+    // Load the client input from a buffer.
+    let _: ClientExecutorRangeInput = bincode::deserialize(&buffer).unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_e2e_execution_witness() {
+    // Intialize the environment variables.
+    dotenv::dotenv().ok();
+
+    // Setup the provider.
+    let rpc_url = Url::parse(std::env::var("RPC_1").unwrap().as_str()).expect("invalid rpc url");
+    let provider = ReqwestProvider::new_http(rpc_url);
+
+    // Setup the host executor.
+    let host_executor = HostExecutor::new(provider).with_execution_witness(true);
+
+    // The node must serve the execution witness, which the host would silently fall back from.
+    let witness =
+        host_executor.fetch_execution_witness(18884864).await.expect("failed to fetch witness");
+    assert!(!witness.state.is_empty());
+
+    // Execute the host on the execution witness.
+    let client_input = host_executor
+        .execute(18884864, ChainVariant::Ethereum)
+        .await
+        .expect("failed to execute host");
+
+    // Execute the client.
+    let (header, _) =
+        ClientExecutor.execute::<EthereumVariant>(client_input).expect("failed to execute client");
+    assert_eq!(header.number, 18884864);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_e2e_pruned_witness() {
+    // Intialize the environment variables.
+    dotenv::dotenv().ok();
+
+    // Setup the provider.
+    let rpc_url = Url::parse(std::env::var("RPC_1").unwrap().as_str()).expect("invalid rpc url");
+    let provider = ReqwestProvider::new_http(rpc_url);
+
+    // Setup the host executor.
+    let host_executor = HostExecutor::new(provider);
+
+    // Execute the host.
+    let mut client_input = host_executor
+        .execute(18884864, ChainVariant::Ethereum)
+        .await
+        .expect("failed to execute host");
+
+    // Prune the sender of the first transaction from the witness.
+    let sender = client_input.current_block.body[0].recover_signer().unwrap();
+    client_input.state_requests.remove(&sender);
+
+    // Execute the client, which must report the missing account with its type.
+    let result = ClientExecutor.execute::<EthereumVariant>(client_input);
+    assert!(
+        matches!(result, Err(ClientError::MissingAccount(address)) if address == sender),
+        "unexpected result: {:?}",
+        result
+    );
+}
+
 async fn run_e2e<V>(variant: ChainVariant, env_var_key: &str, block_number: u64)
 where
     V: Variant,
@@ -53,7 +166,13 @@ where
     let client_executor = ClientExecutor;
 
     // Execute the client.
-    client_executor.execute::<V>(client_input.clone()).expect("failed to execute client");
+    let (header, public_values) =
+        client_executor.execute::<V>(client_input.clone()).expect("failed to execute client");
+    assert_eq!(public_values.block_hash, header.hash_slow());
+
+    // Decode the public values as the host would.
+    let decoded_public_values = decode_public_values(&public_values.abi_encode()).unwrap();
+    assert_eq!(decoded_public_values, public_values);
 
     // Save the client input to a buffer.
     let buffer = bincode::serialize(&client_input).unwrap();
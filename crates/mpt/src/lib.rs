@@ -5,7 +5,22 @@ use serde::{Deserialize, Serialize};
 
 /// Module containing MPT code adapted from `zeth`.
 mod mpt;
-use mpt::{proofs_to_tries, transition_proofs_to_tries, MptNode};
+use mpt::{
+    keccak, multi_transition_proofs_to_tries, node_from_digest, parse_proof, proofs_to_tries,
+    resolve_nodes, transition_proofs_to_tries, MptNode, MptNodeReference, EMPTY_ROOT,
+};
+pub use mpt::{Error as MptError, TrieStats};
+
+/// Errors that can occur when mutating the [EthereumState].
+#[derive(Debug, thiserror::Error)]
+pub enum StateError {
+    #[error("missing storage trie for hashed address: {0}")]
+    MissingStorageTrie(B256),
+    #[error("missing post state storage for hashed address: {0}")]
+    MissingPostStateStorage(B256),
+    #[error("trie error: {0}")]
+    Trie(#[from] MptError),
+}
 
 /// Ethereum state trie and account storage tries.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -25,21 +40,36 @@ impl EthereumState {
             .map_err(|err| eyre::eyre!("{}", err))
     }
 
+    /// Builds Ethereum state tries from relevant proofs before a series of consecutive state
+    /// transitions and the proofs after each of them.
+    pub fn from_multi_transition_proofs(
+        state_root: B256,
+        parent_proofs: &HashMap<Address, AccountProof>,
+        proofs: &[HashMap<Address, AccountProof>],
+    ) -> Result<Self> {
+        multi_transition_proofs_to_tries(state_root, parent_proofs, proofs)
+            .map_err(|err| eyre::eyre!("{}", err))
+    }
+
     /// Builds Ethereum state tries from relevant proofs from a given state.
     pub fn from_proofs(state_root: B256, proofs: &HashMap<Address, AccountProof>) -> Result<Self> {
         proofs_to_tries(state_root, proofs).map_err(|err| eyre::eyre!("{}", err))
     }
 
     /// Mutates state based on diffs provided in [`HashedPostState`].
-    pub fn update(&mut self, post_state: &HashedPostState) {
+    pub fn update(&mut self, post_state: &HashedPostState) -> Result<(), StateError> {
         for (hashed_address, account) in post_state.accounts.iter() {
             let hashed_address = hashed_address.as_slice();
 
             match account {
                 Some(account) => {
-                    let state_storage = &post_state.storages.get(hashed_address).unwrap();
+                    let state_storage = post_state.storages.get(hashed_address).ok_or(
+                        StateError::MissingPostStateStorage(B256::from_slice(hashed_address)),
+                    )?;
                     let storage_root = {
-                        let storage_trie = self.storage_tries.get_mut(hashed_address).unwrap();
+                        let storage_trie = self.storage_tries.get_mut(hashed_address).ok_or(
+                            StateError::MissingStorageTrie(B256::from_slice(hashed_address)),
+                        )?;
 
                         if state_storage.wiped {
                             storage_trie.clear();
@@ -48,9 +78,9 @@ impl EthereumState {
                         for (key, value) in state_storage.storage.iter() {
                             let key = key.as_slice();
                             if value.is_zero() {
-                                storage_trie.delete(key).unwrap();
+                                storage_trie.delete(key)?;
                             } else {
-                                storage_trie.insert_rlp(key, *value).unwrap();
+                                storage_trie.insert_rlp(key, *value)?;
                             }
                         }
 
@@ -63,13 +93,15 @@ impl EthereumState {
                         storage_root,
                         code_hash: account.get_bytecode_hash(),
                     };
-                    self.state_trie.insert_rlp(hashed_address, state_account).unwrap();
+                    self.state_trie.insert_rlp(hashed_address, state_account)?;
                 }
                 None => {
-                    self.state_trie.delete(hashed_address).unwrap();
+                    self.state_trie.delete(hashed_address)?;
                 }
             }
         }
+
+        Ok(())
     }
 
     /// Computes the state root.
@@ -77,3 +109,211 @@ impl EthereumState {
         self.state_trie.hash()
     }
 }
+
+/// Trie nodes indexed by their reference, from which the state trie and the storage tries can be
+/// resolved on demand.
+#[derive(Debug, Clone, Default)]
+pub struct TrieNodes(HashMap<MptNodeReference, MptNode>);
+
+impl TrieNodes {
+    /// Decodes the RLP-encoded preimages of trie nodes.
+    pub fn from_preimages(preimages: &[impl AsRef<[u8]>]) -> Result<Self> {
+        let nodes = parse_proof(preimages)
+            .map_err(|err| eyre::eyre!("invalid node encoding: {}", err))?
+            .into_iter()
+            .map(|node| (node.reference(), node))
+            .collect();
+
+        Ok(Self(nodes))
+    }
+
+    /// Resolves the state trie with the given root, without any storage trie.
+    pub fn state(&self, state_root: B256) -> EthereumState {
+        EthereumState {
+            state_trie: resolve_nodes(&node_from_digest(state_root), &self.0),
+            storage_tries: HashMap::new(),
+        }
+    }
+
+    /// Reads an account from the state trie, resolving its storage trie into the state if it
+    /// hasn't been already.
+    pub fn resolve_account(
+        &self,
+        state: &mut EthereumState,
+        address: &Address,
+    ) -> Result<Option<TrieAccount>> {
+        let hashed_address = keccak(address);
+        let account = state
+            .state_trie
+            .get_rlp::<TrieAccount>(&hashed_address)
+            .map_err(|_| eyre::eyre!("missing state trie nodes for account {}", address))?;
+
+        state.storage_tries.entry(B256::from(hashed_address)).or_insert_with(|| {
+            let storage_root = account.as_ref().map_or(EMPTY_ROOT, |account| account.storage_root);
+            resolve_nodes(&node_from_digest(storage_root), &self.0)
+        });
+
+        Ok(account)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{Bytes, U256};
+    use reth_primitives::KECCAK_EMPTY;
+    use reth_trie::StorageProof;
+
+    use super::*;
+    use crate::mpt::{to_nibs, MptNodeData, RlpBytes};
+
+    /// Returns the proof of a key, made of the encodings of the nodes on its path which are
+    /// referenced by their hash, as returned by `eth_getProof`.
+    fn proof(trie: &MptNode, key: &[u8]) -> Vec<Bytes> {
+        let key_nibs = to_nibs(key);
+        let mut nibs = key_nibs.as_slice();
+        let mut node = trie;
+        let mut proof = Vec::new();
+        loop {
+            if proof.is_empty() || matches!(node.reference(), MptNodeReference::Digest(_)) {
+                proof.push(Bytes::from(node.to_rlp()));
+            }
+            match node.as_data() {
+                MptNodeData::Branch(children) => match &children[nibs[0] as usize] {
+                    Some(child) => {
+                        nibs = &nibs[1..];
+                        node = child;
+                    }
+                    None => break,
+                },
+                MptNodeData::Extension(_, child) => {
+                    nibs = &nibs[node.nibs().len()..];
+                    node = child;
+                }
+                _ => break,
+            }
+        }
+        proof
+    }
+
+    /// Builds a state of 16 accounts, the first of which has 8 storage slots, and returns it with
+    /// the proofs of all the accounts and slots.
+    fn test_state() -> (B256, HashMap<Address, AccountProof>) {
+        let mut storage_trie = MptNode::default();
+        let slots = (1..=8u64).map(U256::from).collect::<Vec<_>>();
+        for slot in &slots {
+            storage_trie
+                .insert_rlp(&keccak(slot.to_be_bytes::<32>()), *slot * U256::from(1000))
+                .unwrap();
+        }
+
+        let addresses = (1..=16u8).map(Address::with_last_byte).collect::<Vec<_>>();
+        let mut state_trie = MptNode::default();
+        for (i, address) in addresses.iter().enumerate() {
+            let account = TrieAccount {
+                nonce: i as u64,
+                balance: U256::from(i),
+                storage_root: if i == 0 { storage_trie.hash() } else { EMPTY_ROOT },
+                code_hash: KECCAK_EMPTY,
+            };
+            state_trie.insert_rlp(&keccak(address), account).unwrap();
+        }
+
+        let proofs = addresses
+            .iter()
+            .enumerate()
+            .map(|(i, address)| {
+                let mut account_proof = AccountProof::new(*address);
+                account_proof.proof = proof(&state_trie, &keccak(address));
+                if i == 0 {
+                    account_proof.storage_root = storage_trie.hash();
+                    account_proof.storage_proofs = slots
+                        .iter()
+                        .map(|slot| {
+                            let key = B256::from(slot.to_be_bytes::<32>());
+                            let mut storage_proof = StorageProof::new(key);
+                            storage_proof.value = *slot * U256::from(1000);
+                            storage_proof.proof = proof(&storage_trie, &keccak(key));
+                            storage_proof
+                        })
+                        .collect();
+                } else {
+                    account_proof.storage_root = EMPTY_ROOT;
+                }
+                (*address, account_proof)
+            })
+            .collect();
+
+        (state_trie.hash(), proofs)
+    }
+
+    #[test]
+    fn test_trie_nodes_round_trip() {
+        let (state_root, proofs) = test_state();
+        let from_proofs = EthereumState::from_proofs(state_root, &proofs).unwrap();
+
+        // Build the same state from the nodes of all the proofs, as found in an execution witness.
+        let preimages = proofs
+            .values()
+            .flat_map(|proof| {
+                proof.proof.iter().chain(proof.storage_proofs.iter().flat_map(|proof| &proof.proof))
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        let nodes = TrieNodes::from_preimages(&preimages).unwrap();
+        let mut from_preimages = nodes.state(state_root);
+        for address in proofs.keys() {
+            let account = nodes.resolve_account(&mut from_preimages, address).unwrap();
+            assert_eq!(
+                account.map(|account| account.storage_root),
+                Some(proofs[address].storage_root)
+            );
+        }
+
+        assert_eq!(from_preimages.state_root(), from_proofs.state_root());
+        assert_eq!(from_preimages.state_trie.stats(), from_proofs.state_trie.stats());
+        assert_eq!(from_preimages.storage_tries.len(), from_proofs.storage_tries.len());
+        for (hashed_address, storage_trie) in &from_proofs.storage_tries {
+            let resolved = &from_preimages.storage_tries[hashed_address];
+            assert_eq!(resolved.hash(), storage_trie.hash());
+            assert_eq!(resolved.stats(), storage_trie.stats());
+        }
+
+        // Every slot can be read from the resolved storage trie.
+        let storage_trie =
+            &from_preimages.storage_tries[&B256::from(keccak(Address::with_last_byte(1)))];
+        for slot in 1..=8u64 {
+            let value = storage_trie
+                .get_rlp::<U256>(&keccak(U256::from(slot).to_be_bytes::<32>()))
+                .unwrap();
+            assert_eq!(value, Some(U256::from(slot * 1000)));
+        }
+    }
+
+    #[test]
+    fn test_resolve_account() {
+        let (state_root, proofs) = test_state();
+        let address = Address::with_last_byte(2);
+        let nodes = TrieNodes::from_preimages(&proofs[&address].proof).unwrap();
+        let mut state = nodes.state(state_root);
+
+        // The account is read, and its empty storage trie resolved.
+        let account = nodes.resolve_account(&mut state, &address).unwrap().unwrap();
+        assert_eq!(account.nonce, 1);
+        assert_eq!(state.storage_tries[&B256::from(keccak(address))].hash(), EMPTY_ROOT);
+
+        // An account whose path is not covered by the nodes can't be read.
+        assert!(nodes.resolve_account(&mut state, &Address::with_last_byte(3)).is_err());
+
+        // An account absent from the trie, proven by the nodes of the whole trie, is read as none.
+        let preimages =
+            proofs.values().flat_map(|proof| proof.proof.iter().cloned()).collect::<Vec<_>>();
+        let nodes = TrieNodes::from_preimages(&preimages).unwrap();
+        let mut state = nodes.state(state_root);
+        let absent = Address::with_last_byte(42);
+        assert_eq!(nodes.resolve_account(&mut state, &absent).unwrap(), None);
+        assert!(state.storage_tries[&B256::from(keccak(absent))].is_empty());
+
+        // Invalid preimages are rejected.
+        assert!(TrieNodes::from_preimages(&[Bytes::from_static(&[0xff])]).is_err());
+    }
+}
@@ -28,7 +28,7 @@ use core::{
     fmt::{Debug, Write},
     iter, mem,
 };
-use reth_trie::AccountProof;
+use reth_trie::{AccountProof, TrieAccount};
 use revm::primitives::HashMap;
 
 use rlp::{Decodable, DecoderError, Prototype, Rlp};
@@ -103,6 +103,30 @@ pub struct MptNode {
     cached_reference: RefCell<Option<MptNodeReference>>,
 }
 
+/// Statistics about the nodes of a sparse Merkle Patricia Trie (MPT), as returned by
+/// [MptNode::stats].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TrieStats {
+    /// The number of resolved nodes.
+    pub nodes: usize,
+    /// The number of resolved leaves.
+    pub leaves: usize,
+    /// The number of unresolved sub-tries, only known by their digest.
+    pub digests: usize,
+    /// The total size of the RLP encodings of the resolved nodes that are referenced by their
+    /// hash, i.e. of the preimages needed to build the trie.
+    pub bytes: usize,
+}
+
+impl core::ops::AddAssign for TrieStats {
+    fn add_assign(&mut self, other: Self) {
+        self.nodes += other.nodes;
+        self.leaves += other.leaves;
+        self.digests += other.digests;
+        self.bytes += other.bytes;
+    }
+}
+
 /// Represents custom error types for the sparse Merkle Patricia Trie (MPT).
 ///
 /// These errors cover various scenarios that can occur during trie operations, such as
@@ -732,6 +756,35 @@ impl MptNode {
         }
     }
 
+    /// Returns statistics about the nodes of the trie.
+    pub fn stats(&self) -> TrieStats {
+        let mut stats = TrieStats::default();
+        self.collect_stats(&mut stats, true);
+        stats
+    }
+
+    fn collect_stats(&self, stats: &mut TrieStats, is_root: bool) {
+        match self.as_data() {
+            MptNodeData::Null => return,
+            MptNodeData::Digest(_) => {
+                stats.digests += 1;
+                return;
+            }
+            MptNodeData::Branch(children) => {
+                children.iter().flatten().for_each(|child| child.collect_stats(stats, false))
+            }
+            MptNodeData::Leaf(_, _) => stats.leaves += 1,
+            MptNodeData::Extension(_, child) => child.collect_stats(stats, false),
+        }
+
+        stats.nodes += 1;
+        // Nodes shorter than 32 bytes are embedded in their parent instead of being referenced by
+        // their hash, so they are not preimages on their own.
+        if is_root || matches!(self.reference(), MptNodeReference::Digest(_)) {
+            stats.bytes += self.length();
+        }
+    }
+
     /// Formats the trie as a string list, where each line corresponds to a trie leaf.
     ///
     /// This method is primarily used for debugging purposes, providing a visual
@@ -1030,6 +1083,16 @@ pub fn transition_proofs_to_tries(
     state_root: B256,
     parent_proofs: &HashMap<Address, AccountProof>,
     proofs: &HashMap<Address, AccountProof>,
+) -> Result<EthereumState> {
+    multi_transition_proofs_to_tries(state_root, parent_proofs, core::slice::from_ref(proofs))
+}
+
+/// Builds state tries from proofs before a series of state transitions and the proofs after each
+/// of them, so that the tries can absorb the updates of every transition in order.
+pub fn multi_transition_proofs_to_tries(
+    state_root: B256,
+    parent_proofs: &HashMap<Address, AccountProof>,
+    proofs: &[HashMap<Address, AccountProof>],
 ) -> Result<EthereumState> {
     // if no addresses are provided, return the trie only consisting of the state root
     if parent_proofs.is_empty() {
@@ -1056,10 +1119,13 @@ pub fn transition_proofs_to_tries(
             state_nodes.insert(node.reference(), node);
         });
 
-        let fini_proofs = proofs.get(address).unwrap();
+        let fini_proofs =
+            proofs.iter().filter_map(|proofs| proofs.get(address)).collect::<Vec<_>>();
 
         // assure that addresses can be deleted from the state trie
-        add_orphaned_leafs(address, &fini_proofs.proof, &mut state_nodes)?;
+        for fini_proof in fini_proofs.iter() {
+            add_orphaned_leafs(address, &fini_proof.proof, &mut state_nodes)?;
+        }
 
         // if no slots are provided, return the trie only consisting of the storage root
         let storage_root = proof.storage_root;
@@ -1086,7 +1152,7 @@ pub fn transition_proofs_to_tries(
         }
 
         // assure that slots can be deleted from the storage trie
-        for storage_proof in &fini_proofs.storage_proofs {
+        for storage_proof in fini_proofs.iter().flat_map(|fini_proof| &fini_proof.storage_proofs) {
             add_orphaned_leafs(storage_proof.key.0, &storage_proof.proof, &mut storage_nodes)?;
         }
         // create the storage trie, from all the relevant nodes
@@ -1122,7 +1188,7 @@ fn add_orphaned_leafs(
 }
 
 /// Creates a new MPT node from a digest.
-fn node_from_digest(digest: B256) -> MptNode {
+pub(crate) fn node_from_digest(digest: B256) -> MptNode {
     match digest {
         EMPTY_ROOT | B256::ZERO => MptNode::default(),
         _ => MptNodeData::Digest(digest).into(),
@@ -1241,6 +1307,48 @@ mod tests {
         assert_eq!(trie.hash(), decoded.hash());
     }
 
+    #[test]
+    pub fn test_stats() {
+        let mut trie = MptNode::default();
+        assert_eq!(trie.stats(), TrieStats::default());
+
+        // a branch with two embedded leaves, and a leaf referenced by its hash
+        trie.insert_rlp(b"a", 0u8).unwrap();
+        trie.insert_rlp(b"b", 1u8).unwrap();
+        trie.insert_rlp(b"c", [0xffu8; 32]).unwrap();
+
+        let stats = trie.stats();
+        assert_eq!(stats.nodes, 5);
+        assert_eq!(stats.leaves, 3);
+        assert_eq!(stats.digests, 0);
+
+        let MptNodeData::Extension(_, branch) = trie.as_data() else {
+            panic!("extension expected")
+        };
+        let MptNodeData::Branch(children) = branch.as_data() else { panic!("branch expected") };
+        let hashed_leaf = children
+            .iter()
+            .flatten()
+            .find(|child| matches!(child.reference(), MptNodeReference::Digest(_)));
+        let hashed_leaf = hashed_leaf.expect("leaf referenced by hash expected");
+        assert_eq!(stats.bytes, trie.length() + branch.length() + hashed_leaf.length());
+
+        // replacing the hashed leaf with its digest leaves it unresolved
+        let mut partial = trie.clone();
+        let MptNodeData::Extension(_, branch) = &mut partial.data else { unreachable!() };
+        let MptNodeData::Branch(children) = &mut branch.data else { unreachable!() };
+        let child = children
+            .iter_mut()
+            .flatten()
+            .find(|child| matches!(child.reference(), MptNodeReference::Digest(_)))
+            .unwrap();
+        **child = MptNodeData::Digest(child.hash()).into();
+
+        let partial_stats = partial.stats();
+        assert_eq!(partial_stats.nodes, 4);
+        assert_eq!(partial_stats.digests, 1);
+    }
+
     #[test]
     pub fn test_partial() {
         let mut trie = MptNode::default();
@@ -1,116 +1,705 @@
+use std::str::FromStr;
+
 use reth_chainspec::{
     BaseFeeParams, BaseFeeParamsKind, Chain, ChainHardforks, ChainSpec, DepositContract,
-    EthereumHardfork, ForkCondition, OptimismHardfork,
+    EthereumHardfork, ForkCondition, Hardfork, OptimismHardfork,
 };
-use reth_primitives::{constants::ETHEREUM_BLOCK_GAS_LIMIT, MAINNET_GENESIS_HASH};
-use revm_primitives::{address, b256, U256};
+use reth_primitives::{constants::ETHEREUM_BLOCK_GAS_LIMIT, Genesis, MAINNET_GENESIS_HASH};
+use revm_primitives::{address, b256, keccak256, Address, B256, U256};
+use serde::{Deserialize, Serialize};
+
+/// The topic of the `DepositEvent` log emitted by the beacon chain deposit contract.
+const DEPOSIT_EVENT_TOPIC: B256 =
+    b256!("649bbc62d0e31342afea4e5cd82d4049e7e1ee912fc0889aa790803be39038c5");
+
+/// A serializable description of a [ChainSpec], containing everything needed to execute blocks.
+///
+/// Unlike [ChainSpec], this can be passed to the client inside its input, and hashed into the
+/// public values so that the verifier knows which rules were applied.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainSpecConfig {
+    /// The chain ID.
+    pub chain_id: u64,
+    /// The genesis block hash, if known.
+    pub genesis_hash: Option<B256>,
+    /// The Paris block number and the final difficulty, if known.
+    pub paris_block_and_final_difficulty: Option<(u64, U256)>,
+    /// The hardfork activation table, in activation order.
+    pub hardforks: Vec<HardforkActivation>,
+    /// The hardforks scheduled on the chain that the pinned execution engine doesn't implement.
+    /// Blocks at or after their activation are rejected instead of being executed with the wrong
+    /// rules.
+    pub unsupported_hardforks: Vec<HardforkActivation>,
+    /// The base fee parameters.
+    pub base_fee_params: BaseFeeParamsConfig,
+    /// The beacon chain deposit contract, if any.
+    pub deposit_contract: Option<DepositContractConfig>,
+    /// The maximum gas limit.
+    pub max_gas_limit: u64,
+    /// Whether the chain supports the DAO fork.
+    pub dao_fork_support: bool,
+}
+
+/// The activation condition of a named hardfork.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HardforkActivation {
+    /// The name of the hardfork, as in [EthereumHardfork] or [OptimismHardfork].
+    pub name: String,
+    /// The activation condition.
+    pub condition: ForkActivation,
+}
+
+/// A serializable [ForkCondition].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ForkActivation {
+    /// Activated at a block number.
+    Block(u64),
+    /// Activated when the total difficulty is reached.
+    Ttd { fork_block: Option<u64>, total_difficulty: U256 },
+    /// Activated at a timestamp.
+    Timestamp(u64),
+    /// Never activated.
+    Never,
+}
+
+/// Serializable base fee parameters.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BaseFeeParamsConfig {
+    /// The same parameters for all blocks.
+    Constant(BaseFeeParamsValues),
+    /// Parameters that change with hardforks, as pairs of hardfork names and parameters.
+    Variable(Vec<(String, BaseFeeParamsValues)>),
+}
+
+/// Serializable [BaseFeeParams].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BaseFeeParamsValues {
+    /// The base fee max change denominator.
+    pub max_change_denominator: u128,
+    /// The elasticity multiplier.
+    pub elasticity_multiplier: u128,
+}
+
+/// Serializable [DepositContract].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DepositContractConfig {
+    /// The deposit contract address.
+    pub address: Address,
+    /// The block at which the deposit contract was deployed.
+    pub block: u64,
+    /// The topic of the deposit event.
+    pub topic: B256,
+}
+
+/// Errors that can occur when converting a [ChainSpecConfig] into a [ChainSpec].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ChainSpecError {
+    #[error("unknown hardfork: {0}")]
+    UnknownHardfork(String),
+    #[error(
+        "unsupported hardfork: {hardfork} is active at block {number} (timestamp {timestamp})"
+    )]
+    UnsupportedHardfork { hardfork: String, number: u64, timestamp: u64 },
+    #[error("invalid genesis config: {0}")]
+    InvalidGenesis(String),
+}
+
+impl HardforkActivation {
+    /// Creates a new [HardforkActivation].
+    pub fn new(name: impl ToString, condition: ForkActivation) -> Self {
+        Self { name: name.to_string(), condition }
+    }
+}
+
+impl From<ForkCondition> for ForkActivation {
+    fn from(condition: ForkCondition) -> Self {
+        match condition {
+            ForkCondition::Block(number) => Self::Block(number),
+            ForkCondition::TTD { fork_block, total_difficulty } => {
+                Self::Ttd { fork_block, total_difficulty }
+            }
+            ForkCondition::Timestamp(timestamp) => Self::Timestamp(timestamp),
+            ForkCondition::Never => Self::Never,
+        }
+    }
+}
+
+impl From<ForkActivation> for ForkCondition {
+    fn from(activation: ForkActivation) -> Self {
+        match activation {
+            ForkActivation::Block(number) => Self::Block(number),
+            ForkActivation::Ttd { fork_block, total_difficulty } => {
+                Self::TTD { fork_block, total_difficulty }
+            }
+            ForkActivation::Timestamp(timestamp) => Self::Timestamp(timestamp),
+            ForkActivation::Never => Self::Never,
+        }
+    }
+}
+
+impl From<BaseFeeParams> for BaseFeeParamsValues {
+    fn from(params: BaseFeeParams) -> Self {
+        Self {
+            max_change_denominator: params.max_change_denominator,
+            elasticity_multiplier: params.elasticity_multiplier,
+        }
+    }
+}
+
+impl From<BaseFeeParamsValues> for BaseFeeParams {
+    fn from(values: BaseFeeParamsValues) -> Self {
+        BaseFeeParams::new(values.max_change_denominator, values.elasticity_multiplier)
+    }
+}
+
+impl ChainSpecConfig {
+    /// Returns the [ChainSpecConfig] for Ethereum mainnet.
+    pub fn mainnet() -> Self {
+        // Spec extracted from:
+        //
+        // https://github.com/paradigmxyz/reth/blob/c228fe15808c3acbf18dc3af1a03ef5cbdcda07a/crates/chainspec/src/spec.rs#L35-L60
+        Self {
+            chain_id: Chain::mainnet().id(),
+            genesis_hash: Some(MAINNET_GENESIS_HASH),
+            paris_block_and_final_difficulty: Some((0, U256::ZERO)),
+            // For some reasons a state root mismatch error arises if we don't force activate
+            // everything before and including Shanghai.
+            hardforks: forced_ethereum_hardforks(1710338135),
+            unsupported_hardforks: prague(1746612311),
+            base_fee_params: BaseFeeParamsConfig::Constant(BaseFeeParams::ethereum().into()),
+            deposit_contract: Some(DepositContractConfig {
+                address: address!("00000000219ab540356cbb839cbe05303d7705fa"),
+                block: 11052984,
+                topic: DEPOSIT_EVENT_TOPIC,
+            }),
+            max_gas_limit: ETHEREUM_BLOCK_GAS_LIMIT,
+            dao_fork_support: true,
+        }
+    }
+
+    /// Returns the [ChainSpecConfig] for OP Mainnet.
+    pub fn op_mainnet() -> Self {
+        // Spec extracted from:
+        //
+        // https://github.com/paradigmxyz/reth/blob/c228fe15808c3acbf18dc3af1a03ef5cbdcda07a/crates/optimism/chainspec/src/op.rs#L18-L44
+        Self {
+            chain_id: Chain::optimism_mainnet().id(),
+            genesis_hash: Some(b256!(
+                "7ca38a1916c42007829c55e69d3e9a73265554b586a499015373241b8a3fa48b"
+            )),
+            paris_block_and_final_difficulty: Some((0, U256::ZERO)),
+            hardforks: hardfork_activations(OptimismHardfork::op_mainnet()),
+            unsupported_hardforks: vec![],
+            base_fee_params: BaseFeeParamsConfig::Variable(vec![
+                (EthereumHardfork::London.to_string(), BaseFeeParams::optimism().into()),
+                (OptimismHardfork::Canyon.to_string(), BaseFeeParams::optimism_canyon().into()),
+            ]),
+            deposit_contract: None,
+            max_gas_limit: ETHEREUM_BLOCK_GAS_LIMIT,
+            dao_fork_support: false,
+        }
+    }
+
+    /// Returns the [ChainSpecConfig] for Linea Mainnet.
+    pub fn linea_mainnet() -> Self {
+        // NOTE: Linea has London activated; but setting Paris tricks reth into disabling
+        //       block rewards, which we need for Linea (clique consensus) to work.
+        Self {
+            chain_id: Chain::linea().id(),
+            genesis_hash: None,
+            paris_block_and_final_difficulty: Some((0, U256::ZERO)),
+            // For some reasons a state root mismatch error arises if we don't force activate
+            // everything before and including Shanghai.
+            hardforks: forced_ethereum_hardforks_until_paris(),
+            unsupported_hardforks: vec![],
+            base_fee_params: BaseFeeParamsConfig::Constant(BaseFeeParams::ethereum().into()),
+            deposit_contract: None,
+            max_gas_limit: ETHEREUM_BLOCK_GAS_LIMIT,
+            dao_fork_support: false,
+        }
+    }
+
+    /// Returns the [ChainSpecConfig] for Sepolia.
+    pub fn sepolia() -> Self {
+        Self {
+            chain_id: Chain::sepolia().id(),
+            genesis_hash: Some(b256!(
+                "25a5cc106eea7138acab33231d7160d69cb777ee0c2c553fcddf5138993e6dd9"
+            )),
+            paris_block_and_final_difficulty: Some((0, U256::ZERO)),
+            hardforks: forced_ethereum_hardforks(1706655072),
+            unsupported_hardforks: prague(1741159776),
+            base_fee_params: BaseFeeParamsConfig::Constant(BaseFeeParams::ethereum().into()),
+            deposit_contract: Some(DepositContractConfig {
+                address: address!("7f02c3e3c98b133055b8b348b2ac625669ed295d"),
+                block: 1273020,
+                topic: DEPOSIT_EVENT_TOPIC,
+            }),
+            max_gas_limit: ETHEREUM_BLOCK_GAS_LIMIT,
+            dao_fork_support: false,
+        }
+    }
+
+    /// Returns the [ChainSpecConfig] for Holesky.
+    pub fn holesky() -> Self {
+        Self {
+            chain_id: Chain::holesky().id(),
+            genesis_hash: Some(b256!(
+                "b5f7f912443c940f21fd611f12828d75b534364ed9e95ca4e307729a4661bde4"
+            )),
+            paris_block_and_final_difficulty: Some((0, U256::ZERO)),
+            hardforks: forced_ethereum_hardforks(1707305664),
+            unsupported_hardforks: prague(1740434112),
+            base_fee_params: BaseFeeParamsConfig::Constant(BaseFeeParams::ethereum().into()),
+            deposit_contract: Some(DepositContractConfig {
+                address: address!("4242424242424242424242424242424242424242"),
+                block: 0,
+                topic: DEPOSIT_EVENT_TOPIC,
+            }),
+            max_gas_limit: ETHEREUM_BLOCK_GAS_LIMIT,
+            dao_fork_support: false,
+        }
+    }
+
+    /// Returns the [ChainSpecConfig] for Base mainnet.
+    pub fn base_mainnet() -> Self {
+        // Spec extracted from:
+        //
+        // https://github.com/paradigmxyz/reth/blob/c228fe15808c3acbf18dc3af1a03ef5cbdcda07a/crates/optimism/chainspec/src/base.rs
+        Self {
+            chain_id: Chain::base_mainnet().id(),
+            genesis_hash: Some(b256!(
+                "f712aa9241cc24369b143cf6dce85f0902a9731e70d66818a3a5845b296c73dd"
+            )),
+            paris_block_and_final_difficulty: Some((0, U256::ZERO)),
+            hardforks: hardfork_activations(OptimismHardfork::base_mainnet()),
+            unsupported_hardforks: vec![],
+            base_fee_params: BaseFeeParamsConfig::Variable(vec![
+                (EthereumHardfork::London.to_string(), BaseFeeParams::optimism().into()),
+                (OptimismHardfork::Canyon.to_string(), BaseFeeParams::optimism_canyon().into()),
+            ]),
+            deposit_contract: None,
+            max_gas_limit: ETHEREUM_BLOCK_GAS_LIMIT,
+            dao_fork_support: false,
+        }
+    }
+
+    /// Returns the [ChainSpecConfig] for OP Sepolia.
+    pub fn op_sepolia() -> Self {
+        // Spec extracted from:
+        //
+        // https://github.com/paradigmxyz/reth/blob/c228fe15808c3acbf18dc3af1a03ef5cbdcda07a/crates/optimism/chainspec/src/op_sepolia.rs
+        Self {
+            chain_id: Chain::optimism_sepolia().id(),
+            genesis_hash: Some(b256!(
+                "102de6ffb001480cc9b8b548fd05c34cd4f46ae4aa91759393db90ea0409887d"
+            )),
+            paris_block_and_final_difficulty: Some((0, U256::ZERO)),
+            hardforks: hardfork_activations(OptimismHardfork::op_sepolia()),
+            unsupported_hardforks: vec![],
+            base_fee_params: BaseFeeParamsConfig::Variable(vec![
+                (EthereumHardfork::London.to_string(), BaseFeeParams::optimism_sepolia().into()),
+                (
+                    OptimismHardfork::Canyon.to_string(),
+                    BaseFeeParams::optimism_sepolia_canyon().into(),
+                ),
+            ]),
+            deposit_contract: None,
+            max_gas_limit: ETHEREUM_BLOCK_GAS_LIMIT,
+            dao_fork_support: false,
+        }
+    }
+
+    /// Builds a [ChainSpecConfig] from a geth-style genesis file, as accepted by `geth init`.
+    ///
+    /// OP stack hardforks and base fee parameters are read from the `bedrockBlock`, `*Time` and
+    /// `optimism` fields of the chain config, following the op-geth format.
+    pub fn from_genesis(genesis: &Genesis) -> Result<Self, ChainSpecError> {
+        let config = &genesis.config;
+
+        let mut hardforks =
+            vec![HardforkActivation::new(EthereumHardfork::Frontier, ForkActivation::Block(0))];
+        let block_forks = [
+            (EthereumHardfork::Homestead, config.homestead_block),
+            (EthereumHardfork::Dao, config.dao_fork_block),
+            (EthereumHardfork::Tangerine, config.eip150_block),
+            (EthereumHardfork::SpuriousDragon, config.eip158_block),
+            (EthereumHardfork::Byzantium, config.byzantium_block),
+            (EthereumHardfork::Constantinople, config.constantinople_block),
+            (EthereumHardfork::Petersburg, config.petersburg_block),
+            (EthereumHardfork::Istanbul, config.istanbul_block),
+            (EthereumHardfork::MuirGlacier, config.muir_glacier_block),
+            (EthereumHardfork::Berlin, config.berlin_block),
+            (EthereumHardfork::London, config.london_block),
+            (EthereumHardfork::ArrowGlacier, config.arrow_glacier_block),
+            (EthereumHardfork::GrayGlacier, config.gray_glacier_block),
+        ];
+        for (fork, block) in block_forks {
+            if let Some(block) = block {
+                hardforks.push(HardforkActivation::new(fork, ForkActivation::Block(block)));
+            }
+        }
+
+        let paris_block_and_final_difficulty = match config.terminal_total_difficulty {
+            Some(total_difficulty) => {
+                hardforks.push(HardforkActivation::new(
+                    EthereumHardfork::Paris,
+                    ForkActivation::Ttd {
+                        fork_block: config.merge_netsplit_block,
+                        total_difficulty,
+                    },
+                ));
+                config.merge_netsplit_block.map(|block| (block, total_difficulty))
+            }
+            None => None,
+        };
+
+        let bedrock_block = extra_field::<u64>(genesis, "bedrockBlock")?;
+        if let Some(block) = bedrock_block {
+            hardforks.push(HardforkActivation::new(
+                OptimismHardfork::Bedrock,
+                ForkActivation::Block(block),
+            ));
+        }
+        let time_forks = [
+            (OptimismHardfork::Regolith.to_string(), extra_field(genesis, "regolithTime")?),
+            (EthereumHardfork::Shanghai.to_string(), config.shanghai_time),
+            (OptimismHardfork::Canyon.to_string(), extra_field(genesis, "canyonTime")?),
+            (EthereumHardfork::Cancun.to_string(), config.cancun_time),
+            (OptimismHardfork::Ecotone.to_string(), extra_field(genesis, "ecotoneTime")?),
+            (OptimismHardfork::Fjord.to_string(), extra_field(genesis, "fjordTime")?),
+            (OptimismHardfork::Granite.to_string(), extra_field(genesis, "graniteTime")?),
+        ];
+        for (fork, timestamp) in time_forks {
+            if let Some(timestamp) = timestamp {
+                hardforks.push(HardforkActivation::new(fork, ForkActivation::Timestamp(timestamp)));
+            }
+        }
+
+        let base_fee_params = match extra_field::<OptimismGenesisInfo>(genesis, "optimism")? {
+            Some(info) if bedrock_block.is_some() => {
+                let london = BaseFeeParamsValues {
+                    max_change_denominator: info.eip1559_denominator,
+                    elasticity_multiplier: info.eip1559_elasticity,
+                };
+                let mut params = vec![(EthereumHardfork::London.to_string(), london)];
+                if let Some(denominator) = info.eip1559_denominator_canyon {
+                    params.push((
+                        OptimismHardfork::Canyon.to_string(),
+                        BaseFeeParamsValues { max_change_denominator: denominator, ..london },
+                    ));
+                }
+                BaseFeeParamsConfig::Variable(params)
+            }
+            _ => BaseFeeParamsConfig::Constant(BaseFeeParams::ethereum().into()),
+        };
+
+        Ok(Self {
+            chain_id: config.chain_id,
+            genesis_hash: None,
+            paris_block_and_final_difficulty,
+            hardforks,
+            unsupported_hardforks: config.prague_time.map(prague).unwrap_or_default(),
+            base_fee_params,
+            deposit_contract: config.deposit_contract_address.map(|address| {
+                DepositContractConfig { address, block: 0, topic: DEPOSIT_EVENT_TOPIC }
+            }),
+            max_gas_limit: ETHEREUM_BLOCK_GAS_LIMIT,
+            dao_fork_support: config.dao_fork_support,
+        })
+    }
+
+    /// Returns whether the chain follows the OP stack rules.
+    pub fn is_optimism(&self) -> bool {
+        self.hardforks.iter().any(|fork| OptimismHardfork::from_str(&fork.name).is_ok())
+    }
+
+    /// Checks that none of the [unsupported hardforks](Self::unsupported_hardforks) is active at
+    /// the block with the given number and timestamp.
+    pub fn check_supported(&self, number: u64, timestamp: u64) -> Result<(), ChainSpecError> {
+        for fork in &self.unsupported_hardforks {
+            let active = match fork.condition {
+                ForkActivation::Block(block) => number >= block,
+                ForkActivation::Timestamp(activation) => timestamp >= activation,
+                ForkActivation::Ttd { .. } | ForkActivation::Never => false,
+            };
+            if active {
+                return Err(ChainSpecError::UnsupportedHardfork {
+                    hardfork: fork.name.clone(),
+                    number,
+                    timestamp,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Computes a commitment to the chain spec, to be included in the public values.
+    pub fn hash(&self) -> B256 {
+        keccak256(serde_json::to_vec(self).expect("failed to serialize chain spec"))
+    }
+}
+
+impl TryFrom<&ChainSpecConfig> for ChainSpec {
+    type Error = ChainSpecError;
+
+    fn try_from(config: &ChainSpecConfig) -> Result<Self, Self::Error> {
+        let hardforks = config
+            .hardforks
+            .iter()
+            .map(|fork| Ok((parse_hardfork(&fork.name)?, fork.condition.into())))
+            .collect::<Result<Vec<_>, ChainSpecError>>()?;
+
+        let base_fee_params = match &config.base_fee_params {
+            BaseFeeParamsConfig::Constant(params) => BaseFeeParamsKind::Constant((*params).into()),
+            BaseFeeParamsConfig::Variable(params) => BaseFeeParamsKind::Variable(
+                params
+                    .iter()
+                    .map(|(fork, params)| Ok((parse_hardfork(fork)?, (*params).into())))
+                    .collect::<Result<Vec<_>, ChainSpecError>>()?
+                    .into(),
+            ),
+        };
+
+        let mut spec = ChainSpec {
+            chain: Chain::from_id(config.chain_id),
+            // We don't need the genesis state. Using default to save cycles.
+            genesis: Default::default(),
+            genesis_hash: config.genesis_hash,
+            paris_block_and_final_difficulty: config.paris_block_and_final_difficulty,
+            hardforks: ChainHardforks::new(hardforks),
+            deposit_contract: config.deposit_contract.map(|contract| {
+                DepositContract::new(contract.address, contract.block, contract.topic)
+            }),
+            base_fee_params,
+            max_gas_limit: config.max_gas_limit,
+            ..Default::default()
+        };
+        spec.genesis.config.dao_fork_support = config.dao_fork_support;
+
+        Ok(spec)
+    }
+}
+
+/// The OP stack specific fields of a genesis chain config.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OptimismGenesisInfo {
+    eip1559_elasticity: u128,
+    eip1559_denominator: u128,
+    eip1559_denominator_canyon: Option<u128>,
+}
+
+/// Reads an optional extra field from the chain config of a genesis file.
+fn extra_field<T: serde::de::DeserializeOwned>(
+    genesis: &Genesis,
+    key: &str,
+) -> Result<Option<T>, ChainSpecError> {
+    genesis
+        .config
+        .extra_fields
+        .get(key)
+        .map(|value| serde_json::from_value(value.clone()))
+        .transpose()
+        .map_err(|err| ChainSpecError::InvalidGenesis(format!("{}: {}", key, err)))
+}
+
+/// Returns the Ethereum hardforks up to and including Paris, all force activated at genesis.
+fn forced_ethereum_hardforks_until_paris() -> Vec<HardforkActivation> {
+    let mut hardforks = [
+        EthereumHardfork::Frontier,
+        EthereumHardfork::Homestead,
+        EthereumHardfork::Dao,
+        EthereumHardfork::Tangerine,
+        EthereumHardfork::SpuriousDragon,
+        EthereumHardfork::Byzantium,
+        EthereumHardfork::Constantinople,
+        EthereumHardfork::Petersburg,
+        EthereumHardfork::Istanbul,
+        EthereumHardfork::MuirGlacier,
+        EthereumHardfork::Berlin,
+        EthereumHardfork::London,
+        EthereumHardfork::ArrowGlacier,
+        EthereumHardfork::GrayGlacier,
+    ]
+    .into_iter()
+    .map(|fork| HardforkActivation::new(fork, ForkActivation::Block(0)))
+    .collect::<Vec<_>>();
+    hardforks.push(HardforkActivation::new(
+        EthereumHardfork::Paris,
+        ForkActivation::Ttd { fork_block: Some(0), total_difficulty: U256::ZERO },
+    ));
+    hardforks
+}
+
+/// Returns the Ethereum hardforks up to and including Shanghai force activated at genesis,
+/// followed by Cancun activated at the given timestamp.
+fn forced_ethereum_hardforks(cancun_timestamp: u64) -> Vec<HardforkActivation> {
+    let mut hardforks = forced_ethereum_hardforks_until_paris();
+    hardforks
+        .push(HardforkActivation::new(EthereumHardfork::Shanghai, ForkActivation::Timestamp(0)));
+    hardforks.push(HardforkActivation::new(
+        EthereumHardfork::Cancun,
+        ForkActivation::Timestamp(cancun_timestamp),
+    ));
+    hardforks
+}
+
+/// Returns Prague activated at the given timestamp, as an unsupported hardfork: the pinned
+/// execution engine only implements a draft of it, which doesn't match the rules of any live
+/// network.
+fn prague(timestamp: u64) -> Vec<HardforkActivation> {
+    vec![HardforkActivation::new(EthereumHardfork::Prague, ForkActivation::Timestamp(timestamp))]
+}
+
+/// Converts a [ChainHardforks] table into [HardforkActivation]s.
+fn hardfork_activations(hardforks: ChainHardforks) -> Vec<HardforkActivation> {
+    hardforks
+        .forks_iter()
+        .map(|(fork, condition)| HardforkActivation::new(fork.name(), condition.into()))
+        .collect()
+}
+
+/// Parses a hardfork name into either an [EthereumHardfork] or an [OptimismHardfork].
+fn parse_hardfork(name: &str) -> Result<Box<dyn Hardfork>, ChainSpecError> {
+    EthereumHardfork::from_str(name)
+        .map(|fork| fork.boxed())
+        .or_else(|_| OptimismHardfork::from_str(name).map(|fork| fork.boxed()))
+        .map_err(|_| ChainSpecError::UnknownHardfork(name.to_string()))
+}
 
 /// Returns the [ChainSpec] for Ethereum mainnet.
 pub fn mainnet() -> ChainSpec {
-    // Spec extracted from:
-    //
-    // https://github.com/paradigmxyz/reth/blob/c228fe15808c3acbf18dc3af1a03ef5cbdcda07a/crates/chainspec/src/spec.rs#L35-L60
-    let mut spec = ChainSpec {
-        chain: Chain::mainnet(),
-        // We don't need the genesis state. Using default to save cycles.
-        genesis: Default::default(),
-        genesis_hash: Some(MAINNET_GENESIS_HASH),
-        paris_block_and_final_difficulty: Some((0, U256::ZERO)),
-        // For some reasons a state root mismatch error arises if we don't force activate everything
-        // before and including Shanghai.
-        hardforks: ChainHardforks::new(vec![
-            (EthereumHardfork::Frontier.boxed(), ForkCondition::Block(0)),
-            (EthereumHardfork::Homestead.boxed(), ForkCondition::Block(0)),
-            (EthereumHardfork::Dao.boxed(), ForkCondition::Block(0)),
-            (EthereumHardfork::Tangerine.boxed(), ForkCondition::Block(0)),
-            (EthereumHardfork::SpuriousDragon.boxed(), ForkCondition::Block(0)),
-            (EthereumHardfork::Byzantium.boxed(), ForkCondition::Block(0)),
-            (EthereumHardfork::Constantinople.boxed(), ForkCondition::Block(0)),
-            (EthereumHardfork::Petersburg.boxed(), ForkCondition::Block(0)),
-            (EthereumHardfork::Istanbul.boxed(), ForkCondition::Block(0)),
-            (EthereumHardfork::MuirGlacier.boxed(), ForkCondition::Block(0)),
-            (EthereumHardfork::Berlin.boxed(), ForkCondition::Block(0)),
-            (EthereumHardfork::London.boxed(), ForkCondition::Block(0)),
-            (EthereumHardfork::ArrowGlacier.boxed(), ForkCondition::Block(0)),
-            (EthereumHardfork::GrayGlacier.boxed(), ForkCondition::Block(0)),
-            (
-                EthereumHardfork::Paris.boxed(),
-                ForkCondition::TTD { fork_block: Some(0), total_difficulty: U256::ZERO },
-            ),
-            (EthereumHardfork::Shanghai.boxed(), ForkCondition::Timestamp(0)),
-            (EthereumHardfork::Cancun.boxed(), ForkCondition::Timestamp(1710338135)),
-        ]),
-        deposit_contract: Some(DepositContract::new(
-            address!("00000000219ab540356cbb839cbe05303d7705fa"),
-            11052984,
-            b256!("649bbc62d0e31342afea4e5cd82d4049e7e1ee912fc0889aa790803be39038c5"),
-        )),
-        base_fee_params: BaseFeeParamsKind::Constant(BaseFeeParams::ethereum()),
-        max_gas_limit: ETHEREUM_BLOCK_GAS_LIMIT,
-        prune_delete_limit: 20000,
-    };
-    spec.genesis.config.dao_fork_support = true;
-    spec
+    (&ChainSpecConfig::mainnet()).try_into().expect("valid chain spec")
 }
 
 /// Returns the [ChainSpec] for OP Mainnet.
 pub fn op_mainnet() -> ChainSpec {
-    // Spec extracted from:
-    //
-    // https://github.com/paradigmxyz/reth/blob/c228fe15808c3acbf18dc3af1a03ef5cbdcda07a/crates/optimism/chainspec/src/op.rs#L18-L44
-    ChainSpec {
-        chain: Chain::optimism_mainnet(),
-        // We don't need the genesis state. Using default to save cycles.
-        genesis: Default::default(),
-        genesis_hash: Some(b256!(
-            "7ca38a1916c42007829c55e69d3e9a73265554b586a499015373241b8a3fa48b"
-        )),
-        paris_block_and_final_difficulty: Some((0, U256::ZERO)),
-        hardforks: OptimismHardfork::op_mainnet(),
-        base_fee_params: BaseFeeParamsKind::Variable(
-            vec![
-                (EthereumHardfork::London.boxed(), BaseFeeParams::optimism()),
-                (OptimismHardfork::Canyon.boxed(), BaseFeeParams::optimism_canyon()),
-            ]
-            .into(),
-        ),
-        max_gas_limit: ETHEREUM_BLOCK_GAS_LIMIT,
-        prune_delete_limit: 10000,
-        ..Default::default()
-    }
+    (&ChainSpecConfig::op_mainnet()).try_into().expect("valid chain spec")
 }
 
 /// Returns the [ChainSpec] for Linea Mainnet.
 pub fn linea_mainnet() -> ChainSpec {
-    // NOTE: Linea has London activated; but setting Paris tricks reth into disabling
-    //       block rewards, which we need for Linea (clique consensus) to work.
-    ChainSpec {
-        chain: Chain::linea(),
-        // We don't need the genesis state. Using default to save cycles.
-        genesis: Default::default(),
-        paris_block_and_final_difficulty: Some((0, U256::ZERO)),
-        // For some reasons a state root mismatch error arises if we don't force activate everything
-        // before and including Shanghai.
-        hardforks: ChainHardforks::new(vec![
-            (EthereumHardfork::Frontier.boxed(), ForkCondition::Block(0)),
-            (EthereumHardfork::Homestead.boxed(), ForkCondition::Block(0)),
-            (EthereumHardfork::Dao.boxed(), ForkCondition::Block(0)),
-            (EthereumHardfork::Tangerine.boxed(), ForkCondition::Block(0)),
-            (EthereumHardfork::SpuriousDragon.boxed(), ForkCondition::Block(0)),
-            (EthereumHardfork::Byzantium.boxed(), ForkCondition::Block(0)),
-            (EthereumHardfork::Constantinople.boxed(), ForkCondition::Block(0)),
-            (EthereumHardfork::Petersburg.boxed(), ForkCondition::Block(0)),
-            (EthereumHardfork::Istanbul.boxed(), ForkCondition::Block(0)),
-            (EthereumHardfork::MuirGlacier.boxed(), ForkCondition::Block(0)),
-            (EthereumHardfork::Berlin.boxed(), ForkCondition::Block(0)),
-            (EthereumHardfork::London.boxed(), ForkCondition::Block(0)),
-            (EthereumHardfork::ArrowGlacier.boxed(), ForkCondition::Block(0)),
-            (EthereumHardfork::GrayGlacier.boxed(), ForkCondition::Block(0)),
-            (
-                EthereumHardfork::Paris.boxed(),
-                ForkCondition::TTD { fork_block: Some(0), total_difficulty: U256::ZERO },
-            ),
-        ]),
-        ..Default::default()
+    (&ChainSpecConfig::linea_mainnet()).try_into().expect("valid chain spec")
+}
+
+/// Returns the [ChainSpec] for Sepolia.
+pub fn sepolia() -> ChainSpec {
+    (&ChainSpecConfig::sepolia()).try_into().expect("valid chain spec")
+}
+
+/// Returns the [ChainSpec] for Holesky.
+pub fn holesky() -> ChainSpec {
+    (&ChainSpecConfig::holesky()).try_into().expect("valid chain spec")
+}
+
+/// Returns the [ChainSpec] for Base mainnet.
+pub fn base_mainnet() -> ChainSpec {
+    (&ChainSpecConfig::base_mainnet()).try_into().expect("valid chain spec")
+}
+
+/// Returns the [ChainSpec] for OP Sepolia.
+pub fn op_sepolia() -> ChainSpec {
+    (&ChainSpecConfig::op_sepolia()).try_into().expect("valid chain spec")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_specs_convert() {
+        for config in [
+            ChainSpecConfig::mainnet(),
+            ChainSpecConfig::op_mainnet(),
+            ChainSpecConfig::linea_mainnet(),
+            ChainSpecConfig::sepolia(),
+            ChainSpecConfig::holesky(),
+            ChainSpecConfig::base_mainnet(),
+            ChainSpecConfig::op_sepolia(),
+        ] {
+            let spec = ChainSpec::try_from(&config).unwrap();
+            assert_eq!(spec.chain.id(), config.chain_id);
+        }
+
+        assert!(!ChainSpecConfig::mainnet().is_optimism());
+        assert!(ChainSpecConfig::op_mainnet().is_optimism());
+        assert!(ChainSpecConfig::base_mainnet().is_optimism());
+        assert!(!ChainSpecConfig::sepolia().is_optimism());
+    }
+
+    #[test]
+    fn test_unsupported_hardforks() {
+        for (config, prague_timestamp) in [
+            (ChainSpecConfig::mainnet(), 1746612311),
+            (ChainSpecConfig::sepolia(), 1741159776),
+            (ChainSpecConfig::holesky(), 1740434112),
+        ] {
+            assert_eq!(config.check_supported(1, prague_timestamp - 1), Ok(()));
+            assert_eq!(
+                config.check_supported(1, prague_timestamp),
+                Err(ChainSpecError::UnsupportedHardfork {
+                    hardfork: EthereumHardfork::Prague.to_string(),
+                    number: 1,
+                    timestamp: prague_timestamp,
+                })
+            );
+        }
+        assert_eq!(ChainSpecConfig::op_mainnet().check_supported(1, u64::MAX), Ok(()));
+    }
+
+    #[test]
+    fn test_from_genesis() {
+        let genesis: Genesis = serde_json::from_str(
+            r#"{
+                "config": {
+                    "chainId": 8453,
+                    "homesteadBlock": 0,
+                    "eip150Block": 0,
+                    "eip155Block": 0,
+                    "eip158Block": 0,
+                    "byzantiumBlock": 0,
+                    "constantinopleBlock": 0,
+                    "petersburgBlock": 0,
+                    "istanbulBlock": 0,
+                    "muirGlacierBlock": 0,
+                    "berlinBlock": 0,
+                    "londonBlock": 0,
+                    "arrowGlacierBlock": 0,
+                    "grayGlacierBlock": 0,
+                    "mergeNetsplitBlock": 0,
+                    "bedrockBlock": 0,
+                    "regolithTime": 0,
+                    "shanghaiTime": 1704992401,
+                    "canyonTime": 1704992401,
+                    "terminalTotalDifficulty": 0,
+                    "terminalTotalDifficultyPassed": true,
+                    "optimism": {
+                        "eip1559Elasticity": 6,
+                        "eip1559Denominator": 50,
+                        "eip1559DenominatorCanyon": 250
+                    }
+                },
+                "difficulty": "0x0",
+                "gasLimit": "0x1c9c380",
+                "alloc": {}
+            }"#,
+        )
+        .unwrap();
+
+        let config = ChainSpecConfig::from_genesis(&genesis).unwrap();
+        assert_eq!(config.chain_id, 8453);
+        assert!(config.is_optimism());
+        assert_eq!(
+            config.base_fee_params,
+            BaseFeeParamsConfig::Variable(vec![
+                (EthereumHardfork::London.to_string(), BaseFeeParams::optimism().into()),
+                (OptimismHardfork::Canyon.to_string(), BaseFeeParams::optimism_canyon().into()),
+            ])
+        );
+
+        let spec = ChainSpec::try_from(&config).unwrap();
+        assert!(spec.is_fork_active_at_timestamp(OptimismHardfork::Canyon, 1704992401));
+        assert_ne!(config.hash(), ChainSpecConfig::op_mainnet().hash());
     }
 }
@@ -0,0 +1,377 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::SystemTime,
+};
+
+use alloy_rpc_types::EIP1186AccountProofResponse;
+use reth_primitives::{keccak256, Address, Bytes, Header, B256};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// The number of blocks below the head of the chain from which blocks are considered final, and
+/// their data can be stored in an [RpcCache].
+pub const CACHE_FINALITY_DEPTH: u64 = 64;
+
+/// A counter making the names of the temporary files written by this process unique.
+static TMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A persistent, content-addressed store of RPC responses shared across blocks.
+///
+/// The store is laid out as follows under its root directory:
+///
+/// - `codes/{code_hash}`: the raw bytecode with the given hash.
+/// - `headers/{block_hash}.json`: the header with the given hash.
+/// - `headers/by-number/{number}`: the hash of the header at the given height.
+/// - `proofs/{number}/{address}-{keys_hash}.json`: the `eth_getProof` response for an account and a
+///   set of storage keys at the given height, where `keys_hash` commits to the sorted keys.
+///
+/// Entries are looked up by height for headers and proofs, so the store must only be used with
+/// blocks that are not subject to reorgs anymore, i.e. at least [CACHE_FINALITY_DEPTH] blocks
+/// below the head of the chain. Entries that fail to be read or verified are
+/// treated as missing, and failures to write are logged and ignored: the store is only ever an
+/// optimization over hitting the network.
+///
+/// The size of the store can be bounded with [RpcCache::with_max_size], in which case
+/// [RpcCache::prune] evicts the least recently used entries.
+#[derive(Debug, Clone)]
+pub struct RpcCache {
+    /// The directory containing the entries.
+    root: PathBuf,
+    /// The maximum total size of the entries, in bytes.
+    max_size: Option<u64>,
+}
+
+/// The outcome of [RpcCache::prune].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PruneStats {
+    /// The number of entries removed.
+    pub removed_entries: usize,
+    /// The total size of the entries removed, in bytes.
+    pub removed_bytes: u64,
+    /// The total size of the entries left, in bytes.
+    pub remaining_bytes: u64,
+}
+
+impl RpcCache {
+    /// Creates a new [RpcCache] storing its entries under `root`. The directory is created lazily.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into(), max_size: None }
+    }
+
+    /// Sets the maximum total size of the entries, in bytes, enforced by [RpcCache::prune].
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Returns the directory containing the entries.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Gets the bytecode with the given hash.
+    pub fn get_code(&self, code_hash: B256) -> Option<Bytes> {
+        let code = Bytes::from(self.read_bytes(&self.code_path(code_hash))?);
+        (keccak256(&code) == code_hash).then_some(code)
+    }
+
+    /// Stores a bytecode under its hash.
+    pub fn put_code(&self, code: &Bytes) {
+        self.write_bytes(&self.code_path(keccak256(code)), code);
+    }
+
+    /// Gets the hash of the block at the given height.
+    pub fn get_block_hash(&self, number: u64) -> Option<B256> {
+        let hash = self.read_bytes(&self.header_number_path(number))?;
+        std::str::from_utf8(&hash).ok()?.trim().parse().ok()
+    }
+
+    /// Gets the header of the block at the given height.
+    pub fn get_header(&self, number: u64) -> Option<Header> {
+        let hash = self.get_block_hash(number)?;
+        let header: Header = self.read_json(&self.header_path(hash))?;
+        (header.number == number && header.hash_slow() == hash).then_some(header)
+    }
+
+    /// Stores a header under its hash, and indexes it by its height.
+    pub fn put_header(&self, hash: B256, header: &Header) {
+        self.write_json(&self.header_path(hash), header);
+        self.write_bytes(&self.header_number_path(header.number), hash.to_string().as_bytes());
+    }
+
+    /// Gets the proof of an account and the given storage keys at the given height.
+    pub fn get_proof(
+        &self,
+        number: u64,
+        address: Address,
+        keys: &[B256],
+    ) -> Option<EIP1186AccountProofResponse> {
+        let proof: EIP1186AccountProofResponse =
+            self.read_json(&self.proof_path(number, address, keys))?;
+        (proof.address == address).then_some(proof)
+    }
+
+    /// Stores the proof of an account and the given storage keys at the given height.
+    pub fn put_proof(&self, number: u64, keys: &[B256], proof: &EIP1186AccountProofResponse) {
+        self.write_json(&self.proof_path(number, proof.address, keys), proof);
+    }
+
+    /// Removes the entries looked up by height for the block at the given height: the index of its
+    /// header and the proofs at its height. Used to discard the data of blocks reorged out of the
+    /// canonical chain.
+    pub fn remove_height(&self, number: u64) -> io::Result<()> {
+        remove_if_exists(&self.header_number_path(number), fs::remove_file)?;
+        remove_if_exists(&self.root.join("proofs").join(number.to_string()), fs::remove_dir_all)
+    }
+
+    /// Evicts the least recently used entries until their total size fits within the maximum
+    /// size, if any.
+    pub fn prune(&self) -> io::Result<PruneStats> {
+        let mut entries = Vec::new();
+        collect_entries(&self.root, &mut entries)?;
+
+        let mut stats = PruneStats {
+            remaining_bytes: entries.iter().map(|(_, size, _)| size).sum(),
+            ..Default::default()
+        };
+        let Some(max_size) = self.max_size else {
+            return Ok(stats);
+        };
+
+        entries.sort_by_key(|(_, _, accessed)| *accessed);
+        for (path, size, _) in entries {
+            if stats.remaining_bytes <= max_size {
+                break;
+            }
+
+            fs::remove_file(&path)?;
+            stats.removed_entries += 1;
+            stats.removed_bytes += size;
+            stats.remaining_bytes -= size;
+        }
+
+        tracing::info!(
+            "pruned {} entries ({} bytes) from the rpc cache, {} bytes left",
+            stats.removed_entries,
+            stats.removed_bytes,
+            stats.remaining_bytes
+        );
+
+        Ok(stats)
+    }
+
+    fn code_path(&self, code_hash: B256) -> PathBuf {
+        self.root.join("codes").join(code_hash.to_string())
+    }
+
+    fn header_path(&self, hash: B256) -> PathBuf {
+        self.root.join("headers").join(format!("{}.json", hash))
+    }
+
+    fn header_number_path(&self, number: u64) -> PathBuf {
+        self.root.join("headers").join("by-number").join(number.to_string())
+    }
+
+    fn proof_path(&self, number: u64, address: Address, keys: &[B256]) -> PathBuf {
+        let mut keys = keys.to_vec();
+        keys.sort_unstable();
+        keys.dedup();
+        let keys_hash = keccak256(keys.iter().flat_map(|key| key.0).collect::<Vec<_>>());
+
+        self.root
+            .join("proofs")
+            .join(number.to_string())
+            .join(format!("{}-{}.json", address, keys_hash))
+    }
+
+    fn read_bytes(&self, path: &Path) -> Option<Vec<u8>> {
+        let bytes = fs::read(path).ok()?;
+
+        // Refresh the modification time, which is used to evict the least recently used entries.
+        if let Err(err) = fs::File::options()
+            .write(true)
+            .open(path)
+            .and_then(|file| file.set_modified(SystemTime::now()))
+        {
+            tracing::debug!("failed to touch rpc cache entry {}: {}", path.display(), err);
+        }
+
+        Some(bytes)
+    }
+
+    fn read_json<V: DeserializeOwned>(&self, path: &Path) -> Option<V> {
+        let bytes = self.read_bytes(path)?;
+        serde_json::from_slice(&bytes)
+            .inspect_err(|err| {
+                tracing::debug!("invalid rpc cache entry {}: {}", path.display(), err)
+            })
+            .ok()
+    }
+
+    fn write_bytes(&self, path: &Path, bytes: &[u8]) {
+        if let Err(err) = write_atomic(path, bytes) {
+            tracing::warn!("failed to write rpc cache entry {}: {}", path.display(), err);
+        }
+    }
+
+    fn write_json<V: Serialize>(&self, path: &Path, value: &V) {
+        match serde_json::to_vec(value) {
+            Ok(bytes) => self.write_bytes(path, &bytes),
+            Err(err) => tracing::warn!("failed to serialize rpc cache entry: {}", err),
+        }
+    }
+}
+
+/// Writes a file through a temporary file, so that concurrent readers never see partial entries.
+fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let dir = path.parent().expect("cache entries are in a directory");
+    fs::create_dir_all(dir)?;
+
+    // Concurrent writes of the same entry, from this process or others, each use their own file.
+    let tmp_path = path.with_extension(format!(
+        "tmp-{}-{}",
+        std::process::id(),
+        TMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let mut file = fs::File::create(&tmp_path)?;
+    file.write_all(bytes)?;
+    drop(file);
+
+    fs::rename(tmp_path, path)
+}
+
+/// Removes a file or directory with `remove`, ignoring it if it doesn't exist.
+fn remove_if_exists(path: &Path, remove: impl FnOnce(&Path) -> io::Result<()>) -> io::Result<()> {
+    match remove(path) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+/// Recursively collects the path, size and modification time of the files under `dir`.
+fn collect_entries(dir: &Path, entries: &mut Vec<(PathBuf, u64, SystemTime)>) -> io::Result<()> {
+    let read_dir = match fs::read_dir(dir) {
+        Ok(read_dir) => read_dir,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+
+    for entry in read_dir {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            collect_entries(&entry.path(), entries)?;
+        } else {
+            entries.push((entry.path(), metadata.len(), metadata.modified()?));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    /// Returns a cache in an empty directory unique to the test.
+    fn test_cache(name: &str) -> RpcCache {
+        let root = std::env::temp_dir().join(format!(
+            "rsp-rpc-cache-test-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&root);
+        RpcCache::new(root)
+    }
+
+    #[test]
+    fn test_get_put() {
+        let cache = test_cache("get-put");
+
+        let code = Bytes::from_static(&[0x60, 0x00, 0x60, 0x00, 0xf3]);
+        assert_eq!(cache.get_code(keccak256(&code)), None);
+        cache.put_code(&code);
+        assert_eq!(cache.get_code(keccak256(&code)), Some(code));
+
+        let header = Header { number: 42, ..Default::default() };
+        let hash = header.hash_slow();
+        assert_eq!(cache.get_header(42), None);
+        cache.put_header(hash, &header);
+        assert_eq!(cache.get_block_hash(42), Some(hash));
+        assert_eq!(cache.get_header(42), Some(header));
+        assert_eq!(cache.get_header(43), None);
+
+        let address = Address::repeat_byte(1);
+        let keys = [B256::repeat_byte(2), B256::repeat_byte(3)];
+        let proof = EIP1186AccountProofResponse { address, ..Default::default() };
+        cache.put_proof(42, &keys, &proof);
+        // Proofs are looked up by the set of keys, regardless of their order.
+        assert_eq!(cache.get_proof(42, address, &[keys[1], keys[0]]), Some(proof));
+        assert_eq!(cache.get_proof(42, address, &keys[..1]), None);
+        assert_eq!(cache.get_proof(43, address, &keys), None);
+
+        // No temporary file is left behind.
+        let mut entries = Vec::new();
+        collect_entries(cache.root(), &mut entries).unwrap();
+        assert_eq!(entries.len(), 4);
+    }
+
+    #[test]
+    fn test_remove_height() {
+        let cache = test_cache("remove-height");
+
+        let address = Address::repeat_byte(1);
+        let proof = EIP1186AccountProofResponse { address, ..Default::default() };
+        for number in [42, 43] {
+            let header = Header { number, ..Default::default() };
+            cache.put_header(header.hash_slow(), &header);
+            cache.put_proof(number, &[], &proof);
+        }
+
+        cache.remove_height(42).unwrap();
+        assert_eq!(cache.get_block_hash(42), None);
+        assert_eq!(cache.get_proof(42, address, &[]), None);
+        assert!(!cache.root().join("proofs").join("42").exists());
+        assert!(cache.get_header(43).is_some());
+        assert!(cache.get_proof(43, address, &[]).is_some());
+
+        // Removing a height with no entries is a no-op.
+        cache.remove_height(42).unwrap();
+    }
+
+    #[test]
+    fn test_prune() {
+        let cache = test_cache("prune");
+
+        // Store three codes of 100 bytes, accessed in the order 1, 0, 2.
+        let codes = (0..3u8).map(|byte| Bytes::from(vec![byte; 100])).collect::<Vec<_>>();
+        let now = SystemTime::now();
+        for (code, age) in codes.iter().zip([2, 3, 1]) {
+            cache.put_code(code);
+            fs::File::options()
+                .write(true)
+                .open(cache.code_path(keccak256(code)))
+                .unwrap()
+                .set_modified(now - Duration::from_secs(age * 60))
+                .unwrap();
+        }
+
+        // Without a maximum size, nothing is evicted.
+        let stats = cache.prune().unwrap();
+        assert_eq!(stats, PruneStats { remaining_bytes: 300, ..Default::default() });
+
+        // The least recently used entries are evicted first, until the entries fit.
+        let stats = cache.clone().with_max_size(150).prune().unwrap();
+        assert_eq!(
+            stats,
+            PruneStats { removed_entries: 2, removed_bytes: 200, remaining_bytes: 100 }
+        );
+        assert!(!cache.code_path(keccak256(&codes[1])).exists());
+        assert!(!cache.code_path(keccak256(&codes[0])).exists());
+        assert!(cache.code_path(keccak256(&codes[2])).exists());
+    }
+}
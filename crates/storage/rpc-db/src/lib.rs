@@ -1,21 +1,36 @@
 use std::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
     collections::{BTreeMap, BTreeSet},
     marker::PhantomData,
 };
 
 use alloy_provider::{network::AnyNetwork, Provider};
-use alloy_rpc_types::BlockId;
+use alloy_rpc_types::{BlockId, EIP1186AccountProofResponse};
 use alloy_transport::Transport;
+use futures::{StreamExt, TryStreamExt};
 use reth_primitives::{
     revm_primitives::{AccountInfo, Bytecode},
-    Address, B256, U256,
+    Address, Bytes, Header, B256, KECCAK_EMPTY, U256,
 };
 use reth_revm::DatabaseRef;
 use reth_storage_errors::{db::DatabaseError, provider::ProviderError};
 use revm_primitives::HashMap;
 
+mod cache;
+pub use cache::{PruneStats, RpcCache, CACHE_FINALITY_DEPTH};
+
+/// The default maximum number of in-flight requests issued while prefetching.
+pub const DEFAULT_PREFETCH_CONCURRENCY: usize = 16;
+
+/// The number of calls bundled into a single JSON-RPC batch request while prefetching.
+const PREFETCH_BATCH_SIZE: usize = 32;
+
 /// A database that fetches data from a [Provider] over a [Transport].
+///
+/// Values are fetched lazily when read during execution. To avoid one round trip per value, the
+/// keys known to be read can be fetched ahead of time with [RpcDb::prefetch], and the remaining
+/// ones discovered with [RpcDb::speculate]. Accounts and block hashes are also looked up in an
+/// optional persistent [RpcCache] before hitting the network.
 #[derive(Debug, Clone)]
 pub struct RpcDb<T, P> {
     /// The provider which fetches data.
@@ -28,10 +43,51 @@ pub struct RpcDb<T, P> {
     pub storage: RefCell<HashMap<Address, HashMap<U256, U256>>>,
     /// The oldest block whose header/hash has been requested.
     pub oldest_ancestor: RefCell<u64>,
+    /// The maximum number of in-flight requests issued while prefetching.
+    pub prefetch_concurrency: usize,
+    /// The persistent cache consulted before fetching accounts and block hashes.
+    pub cache: Option<RpcCache>,
+    /// Whether reads of values not fetched yet are recorded instead of fetched.
+    speculating: Cell<bool>,
+    /// The keys read while speculating that have not been fetched yet.
+    missed_keys: RefCell<StateKeys>,
     /// A phantom type to make the struct generic over the transport.
     pub _phantom: PhantomData<T>,
 }
 
+/// A set of account and storage keys to fetch with [RpcDb::prefetch].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateKeys {
+    /// The addresses of the accounts.
+    pub accounts: BTreeSet<Address>,
+    /// The storage slots, keyed by account address and index.
+    pub storage: BTreeSet<(Address, U256)>,
+}
+
+impl StateKeys {
+    /// Returns `true` if there are no keys.
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty() && self.storage.is_empty()
+    }
+}
+
+/// Puts an [RpcDb] in speculative mode until dropped, so that it is left again even if the
+/// speculative execution panics.
+struct SpeculationGuard<'a>(&'a Cell<bool>);
+
+impl<'a> SpeculationGuard<'a> {
+    fn new(speculating: &'a Cell<bool>) -> Self {
+        speculating.set(true);
+        Self(speculating)
+    }
+}
+
+impl Drop for SpeculationGuard<'_> {
+    fn drop(&mut self) {
+        self.0.set(false);
+    }
+}
+
 /// Errors that can occur when interacting with the [RpcDb].
 #[derive(Debug, Clone, thiserror::Error)]
 pub enum RpcDbError {
@@ -52,12 +108,158 @@ impl<T: Transport + Clone, P: Provider<T, AnyNetwork> + Clone> RpcDb<T, P> {
             accounts: RefCell::new(HashMap::new()),
             storage: RefCell::new(HashMap::new()),
             oldest_ancestor: RefCell::new(block),
+            prefetch_concurrency: DEFAULT_PREFETCH_CONCURRENCY,
+            cache: None,
+            speculating: Cell::new(false),
+            missed_keys: RefCell::new(StateKeys::default()),
             _phantom: PhantomData,
         }
     }
 
+    /// Sets the maximum number of in-flight requests issued while prefetching.
+    pub fn with_prefetch_concurrency(mut self, prefetch_concurrency: usize) -> Self {
+        self.prefetch_concurrency = prefetch_concurrency.max(1);
+        self
+    }
+
+    /// Sets the persistent cache consulted before fetching accounts and block hashes.
+    pub fn with_cache(mut self, cache: RpcCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Fetches the given accounts and storage slots ahead of execution, populating
+    /// [RpcDb::accounts] and [RpcDb::storage].
+    ///
+    /// Keys are bundled into JSON-RPC batch requests, up to
+    /// [prefetch_concurrency](RpcDb::prefetch_concurrency) of which are in flight at once. Values
+    /// that have already been fetched are skipped.
+    pub async fn prefetch(&self, keys: &StateKeys) -> Result<(), RpcDbError> {
+        let addresses = {
+            let accounts = self.accounts.borrow();
+            keys.accounts
+                .iter()
+                .filter(|address| !accounts.contains_key(*address))
+                .copied()
+                .collect::<Vec<_>>()
+        };
+        let slots = {
+            let storage = self.storage.borrow();
+            keys.storage
+                .iter()
+                .filter(|(address, index)| {
+                    !storage.get(address).is_some_and(|slots| slots.contains_key(index))
+                })
+                .copied()
+                .collect::<Vec<_>>()
+        };
+        if addresses.is_empty() && slots.is_empty() {
+            return Ok(());
+        }
+
+        tracing::info!(
+            "prefetching {} accounts and {} storage slots",
+            addresses.len(),
+            slots.len()
+        );
+
+        futures::stream::iter(addresses.chunks(PREFETCH_BATCH_SIZE))
+            .map(|addresses| self.fetch_account_infos(addresses))
+            .buffer_unordered(self.prefetch_concurrency)
+            .try_collect::<()>()
+            .await?;
+
+        futures::stream::iter(slots.chunks(PREFETCH_BATCH_SIZE))
+            .map(|slots| self.fetch_storage_values(slots))
+            .buffer_unordered(self.prefetch_concurrency)
+            .try_collect::<()>()
+            .await?;
+
+        Ok(())
+    }
+
+    /// Runs `f` with the database in speculative mode, and returns its result along with the keys
+    /// it read that have not been fetched yet.
+    ///
+    /// While speculating, such reads are answered with empty accounts and zero storage values
+    /// instead of being fetched, so that a block can be executed without any round trip to
+    /// discover the state it touches. The outcome of `f` is therefore only meaningful for
+    /// collecting keys, which can then be passed to [RpcDb::prefetch].
+    pub fn speculate<R>(&self, f: impl FnOnce() -> R) -> (R, StateKeys) {
+        let result = {
+            let _guard = SpeculationGuard::new(&self.speculating);
+            f()
+        };
+
+        (result, self.missed_keys.take())
+    }
+
+    /// Fetch the [AccountInfo]s for a batch of [Address]es in a single JSON-RPC batch request.
+    async fn fetch_account_infos(&self, addresses: &[Address]) -> Result<(), RpcDbError> {
+        let client = self.provider.client();
+        let mut batch = client.new_batch();
+
+        let mut waiters = Vec::with_capacity(addresses.len());
+        for &address in addresses {
+            if self.cached_account_info(address).is_some() {
+                continue;
+            }
+
+            let proof = batch
+                .add_call::<_, EIP1186AccountProofResponse>(
+                    "eth_getProof",
+                    &(address, Vec::<B256>::new(), self.block),
+                )
+                .map_err(|e| RpcDbError::RpcError(e.to_string()))?;
+            let code = batch
+                .add_call::<_, Bytes>("eth_getCode", &(address, self.block))
+                .map_err(|e| RpcDbError::RpcError(e.to_string()))?;
+            waiters.push((address, proof, code));
+        }
+        if waiters.is_empty() {
+            return Ok(());
+        }
+
+        batch.send().await.map_err(|e| RpcDbError::RpcError(e.to_string()))?;
+
+        for (address, proof, code) in waiters {
+            let proof = proof.await.map_err(|e| RpcDbError::RpcError(e.to_string()))?;
+            let code = code.await.map_err(|e| RpcDbError::RpcError(e.to_string()))?;
+            self.record_account_info(address, proof, code);
+        }
+
+        Ok(())
+    }
+
+    /// Fetch a batch of storage values in a single JSON-RPC batch request.
+    async fn fetch_storage_values(&self, slots: &[(Address, U256)]) -> Result<(), RpcDbError> {
+        let client = self.provider.client();
+        let mut batch = client.new_batch();
+
+        let mut waiters = Vec::with_capacity(slots.len());
+        for &(address, index) in slots {
+            let value = batch
+                .add_call::<_, U256>("eth_getStorageAt", &(address, index, self.block))
+                .map_err(|e| RpcDbError::RpcError(e.to_string()))?;
+            waiters.push((address, index, value));
+        }
+
+        batch.send().await.map_err(|e| RpcDbError::RpcError(e.to_string()))?;
+
+        for (address, index, value) in waiters {
+            let value = value.await.map_err(|e| RpcDbError::RpcError(e.to_string()))?;
+            self.storage.borrow_mut().entry(address).or_default().insert(index, value);
+        }
+
+        Ok(())
+    }
+
     /// Fetch the [AccountInfo] for an [Address].
     pub async fn fetch_account_info(&self, address: Address) -> Result<AccountInfo, RpcDbError> {
+        if let Some(account_info) = self.cached_account_info(address) {
+            return Ok(account_info);
+        }
+
         tracing::info!("fetching account info for address: {}", address);
 
         // Fetch the proof for the account.
@@ -76,19 +278,56 @@ impl<T: Transport + Clone, P: Provider<T, AnyNetwork> + Clone> RpcDb<T, P> {
             .await
             .map_err(|e| RpcDbError::RpcError(e.to_string()))?;
 
-        // Construct the account info & write it to the log.
+        Ok(self.record_account_info(address, proof, code))
+    }
+
+    /// Construct the [AccountInfo] from its proof and code stored in the [RpcCache], if any, and
+    /// record it to the state.
+    fn cached_account_info(&self, address: Address) -> Option<AccountInfo> {
+        let cache = self.cache.as_ref()?;
+        let proof = cache.get_proof(self.block.as_u64()?, address, &[])?;
+        let code = if proof.code_hash == KECCAK_EMPTY || proof.code_hash == B256::ZERO {
+            Bytes::new()
+        } else {
+            cache.get_code(proof.code_hash)?
+        };
+
+        Some(self.insert_account_info(address, proof, code))
+    }
+
+    /// Construct the [AccountInfo] from its proof and code, record it to the state and store them
+    /// in the [RpcCache], if any.
+    fn record_account_info(
+        &self,
+        address: Address,
+        proof: EIP1186AccountProofResponse,
+        code: Bytes,
+    ) -> AccountInfo {
+        if let (Some(cache), Some(number)) = (&self.cache, self.block.as_u64()) {
+            cache.put_proof(number, &[], &proof);
+            cache.put_code(&code);
+        }
+
+        self.insert_account_info(address, proof, code)
+    }
+
+    fn insert_account_info(
+        &self,
+        address: Address,
+        proof: EIP1186AccountProofResponse,
+        code: Bytes,
+    ) -> AccountInfo {
         let bytecode = Bytecode::new_raw(code);
         let account_info = AccountInfo {
             nonce: proof.nonce,
             balance: proof.balance,
             code_hash: proof.code_hash,
-            code: Some(bytecode.clone()),
+            code: Some(bytecode),
         };
 
-        // Record the account info to the state.
         self.accounts.borrow_mut().insert(address, account_info.clone());
 
-        Ok(account_info)
+        account_info
     }
 
     /// Fetch the storage value at an [Address] and [U256] index.
@@ -117,19 +356,33 @@ impl<T: Transport + Clone, P: Provider<T, AnyNetwork> + Clone> RpcDb<T, P> {
 
     /// Fetch the block hash for a block number.
     pub async fn fetch_block_hash(&self, number: u64) -> Result<B256, RpcDbError> {
-        tracing::info!("fetching block hash for block number: {}", number);
+        let hash = match self.cache.as_ref().and_then(|cache| cache.get_block_hash(number)) {
+            Some(hash) => hash,
+            None => {
+                tracing::info!("fetching block hash for block number: {}", number);
 
-        // Fetch the block.
-        let block = self
-            .provider
-            .get_block_by_number(number.into(), false)
-            .await
-            .map_err(|e| RpcDbError::RpcError(e.to_string()))?;
+                // Fetch the block.
+                let block = self
+                    .provider
+                    .get_block_by_number(number.into(), false)
+                    .await
+                    .map_err(|e| RpcDbError::RpcError(e.to_string()))?;
+
+                // Record the block hash to the state.
+                let block = block.ok_or(RpcDbError::BlockNotFound)?;
+                let hash = block.header.hash;
 
-        // Record the block hash to the state.
-        let block = block.ok_or(RpcDbError::BlockNotFound)?;
-        let hash = block.header.hash;
+                if let Some(cache) = &self.cache {
+                    if let Ok(header) = Header::try_from(block.inner.header) {
+                        cache.put_header(hash, &header);
+                    }
+                }
 
+                hash
+            }
+        };
+
+        // Record the oldest ancestor, whose header has to be part of the witness.
         let mut oldest_ancestor = self.oldest_ancestor.borrow_mut();
         *oldest_ancestor = number.min(*oldest_ancestor);
 
@@ -173,6 +426,17 @@ impl<T: Transport + Clone, P: Provider<T, AnyNetwork> + Clone> DatabaseRef for R
     type Error = ProviderError;
 
     fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        if let Some(account_info) = self.accounts.borrow().get(&address) {
+            return Ok(Some(account_info.clone()));
+        }
+        if let Some(account_info) = self.cached_account_info(address) {
+            return Ok(Some(account_info));
+        }
+        if self.speculating.get() {
+            self.missed_keys.borrow_mut().accounts.insert(address);
+            return Ok(None);
+        }
+
         let handle = tokio::runtime::Handle::try_current().map_err(|_| {
             ProviderError::Database(DatabaseError::Other("no tokio runtime found".to_string()))
         })?;
@@ -188,6 +452,15 @@ impl<T: Transport + Clone, P: Provider<T, AnyNetwork> + Clone> DatabaseRef for R
     }
 
     fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        if let Some(value) = self.storage.borrow().get(&address).and_then(|slots| slots.get(&index))
+        {
+            return Ok(*value);
+        }
+        if self.speculating.get() {
+            self.missed_keys.borrow_mut().storage.insert((address, index));
+            return Ok(U256::ZERO);
+        }
+
         let handle = tokio::runtime::Handle::try_current().map_err(|_| {
             ProviderError::Database(DatabaseError::Other("no tokio runtime found".to_string()))
         })?;
@@ -1,8 +1,10 @@
+use std::sync::Mutex;
+
 use reth_primitives::{
     revm_primitives::{db::DatabaseRef, AccountInfo, Bytecode},
     B256,
 };
-use reth_storage_errors::provider::ProviderError;
+use reth_storage_errors::{db::DatabaseError, provider::ProviderError};
 use revm_primitives::{Address, HashMap, U256};
 use serde::{Deserialize, Serialize};
 
@@ -15,28 +17,87 @@ pub struct WitnessDb {
     pub storage: HashMap<Address, HashMap<U256, U256>>,
     /// The block hashes, indexed by block number.
     pub block_hashes: HashMap<u64, B256>,
+    /// The last error returned by the database. Errors are stringified on their way through reth,
+    /// so they are kept here to be reported with their type once execution fails.
+    #[serde(skip)]
+    last_error: Mutex<Option<WitnessDbError>>,
+}
+
+impl WitnessDb {
+    /// Creates a new [WitnessDb] from the witnessed accounts, storage values and block hashes.
+    pub fn new(
+        accounts: HashMap<Address, AccountInfo>,
+        storage: HashMap<Address, HashMap<U256, U256>>,
+        block_hashes: HashMap<u64, B256>,
+    ) -> Self {
+        Self { accounts, storage, block_hashes, last_error: Mutex::default() }
+    }
+
+    /// Takes the last error returned by the database, if any.
+    pub fn take_error(&self) -> Option<WitnessDbError> {
+        self.last_error.lock().unwrap().take()
+    }
+
+    /// Records an error before returning it.
+    fn record(&self, error: WitnessDbError) -> WitnessDbError {
+        *self.last_error.lock().unwrap() = Some(error.clone());
+        error
+    }
+}
+
+/// Errors that can occur when reading from the [WitnessDb].
+///
+/// All of them indicate that the witness does not cover the state accessed during execution.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum WitnessDbError {
+    #[error("missing account in witness: {0}")]
+    MissingAccount(Address),
+    #[error("missing storage slot in witness: address={0}, slot={1}")]
+    MissingSlot(Address, U256),
+    #[error("missing block hash in witness: {0}")]
+    MissingBlockHash(u64),
+    #[error("missing bytecode in witness: {0}")]
+    MissingBytecode(B256),
+}
+
+impl From<WitnessDbError> for ProviderError {
+    fn from(error: WitnessDbError) -> Self {
+        ProviderError::Database(DatabaseError::Other(error.to_string()))
+    }
 }
 
 impl DatabaseRef for WitnessDb {
-    type Error = ProviderError;
+    type Error = WitnessDbError;
 
     fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
         // Even absent accounts are loaded as `None`, so if an entry is missing from `HashMap` we
-        // need to panic. Otherwise it would be interpreted by `revm` as an uninitialized account.
-        Ok(Some(self.accounts.get(&address).cloned().unwrap()))
+        // need to fail. Otherwise it would be interpreted by `revm` as an uninitialized account.
+        let account = self
+            .accounts
+            .get(&address)
+            .ok_or_else(|| self.record(WitnessDbError::MissingAccount(address)))?;
+        Ok(Some(account.clone()))
     }
 
-    fn code_by_hash_ref(&self, _code_hash: B256) -> Result<Bytecode, Self::Error> {
-        unimplemented!()
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        // Bytecodes are always loaded alongside their accounts.
+        Err(self.record(WitnessDbError::MissingBytecode(code_hash)))
     }
 
     fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
         // Absence of storage trie or slot must be treated as an error here. Otherwise it's possible
         // to trick `revm` into believing a slot is `0` when it's not.
-        Ok(*self.storage.get(&address).unwrap().get(&index).unwrap())
+        self.storage
+            .get(&address)
+            .and_then(|storage| storage.get(&index))
+            .copied()
+            .ok_or_else(|| self.record(WitnessDbError::MissingSlot(address, index)))
     }
 
     fn block_hash_ref(&self, number: u64) -> Result<B256, Self::Error> {
-        Ok(*self.block_hashes.get(&number).unwrap())
+        self.block_hashes
+            .get(&number)
+            .copied()
+            .ok_or_else(|| self.record(WitnessDbError::MissingBlockHash(number)))
     }
 }